@@ -29,12 +29,17 @@ impl<T> DangerCell<T> {
 
         AccessGuard(self)
     }
+
+    /// Take back the stored value
+    pub fn into_inner(self) -> T {
+        self.storage.into_inner()
+    }
 }
 
 /// Scope guard for keeping track of the borrowed state
 pub struct AccessGuard<'a, T>(&'a DangerCell<T>);
 
-impl<'a, T> Deref for AccessGuard<'a, T> {
+impl<T> Deref for AccessGuard<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -42,13 +47,13 @@ impl<'a, T> Deref for AccessGuard<'a, T> {
     }
 }
 
-impl<'a, T> DerefMut for AccessGuard<'a, T> {
+impl<T> DerefMut for AccessGuard<'_, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe { &mut *self.0.storage.get() }
     }
 }
 
-impl<'a, T> Drop for AccessGuard<'a, T> {
+impl<T> Drop for AccessGuard<'_, T> {
     fn drop(&mut self) {
         self.0.borrowed.set(false);
     }
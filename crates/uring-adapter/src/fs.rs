@@ -0,0 +1,707 @@
+//! Files tied to a [`Reactor`], reading and writing at explicit offsets
+//! through owned buffers that stay alive while the kernel uses them
+
+use std::{
+    alloc::Layout,
+    ffi::CString,
+    io::{Error, ErrorKind, Result},
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd, RawFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+    ptr::NonNull,
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+use io_uring::{opcode::Close, types::Fd};
+pub use uring_operation::IoBuffer;
+use uring_operation::{Fsync, Ftruncate, OpenAt, Operation, ReadAt, Statx, WriteAt};
+use uring_reactor::Reactor;
+
+/// Alignment assumed for direct I/O when the file system doesn't report it
+const DEFAULT_DIRECT_ALIGNMENT: usize = 512;
+
+/// Options for opening a [`File`], mirroring [`std::fs::OpenOptions`]
+#[derive(Clone, Debug)]
+#[must_use]
+#[allow(clippy::struct_excessive_bools)]
+pub struct OpenOptions {
+    read: bool,
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+    create_new: bool,
+    direct: bool,
+    mode: u32,
+    custom_flags: i32,
+}
+
+impl OpenOptions {
+    /// Options with every flag disabled and a mode of `0o666`
+    pub const fn new() -> Self {
+        Self {
+            read: false,
+            write: false,
+            append: false,
+            truncate: false,
+            create: false,
+            create_new: false,
+            direct: false,
+            mode: 0o666,
+            custom_flags: 0,
+        }
+    }
+
+    pub const fn read(&mut self, read: bool) -> &mut Self {
+        self.read = read;
+        self
+    }
+
+    pub const fn write(&mut self, write: bool) -> &mut Self {
+        self.write = write;
+        self
+    }
+
+    /// Write to the end of the file regardless of the given offsets
+    pub const fn append(&mut self, append: bool) -> &mut Self {
+        self.append = append;
+        self
+    }
+
+    pub const fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    pub const fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    /// Create the file and fail if it already exists
+    pub const fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.create_new = create_new;
+        self
+    }
+
+    /// Bypass the page cache through `O_DIRECT`, which requires buffers,
+    /// lengths and offsets to be aligned, see [`File::alignment`]
+    pub const fn direct(&mut self, direct: bool) -> &mut Self {
+        self.direct = direct;
+        self
+    }
+
+    /// Permissions of newly created files before applying the umask
+    pub const fn mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Additional `open(2)` flags, apart from the access mode
+    pub const fn custom_flags(&mut self, flags: i32) -> &mut Self {
+        self.custom_flags = flags;
+        self
+    }
+
+    /// Open the file at the path through `IORING_OP_OPENAT`
+    ///
+    /// # Errors
+    ///
+    /// If the options are contradictory, the path contains a nul byte or
+    /// opening fails
+    pub async fn open(&self, reactor: Rc<Reactor>, path: impl AsRef<Path>) -> Result<File> {
+        let flags = self.flags()?;
+        let mode = self.mode;
+        let path = CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "path contains a nul byte"))?;
+
+        let file = OpenAt::new(path)
+            .flags(flags)
+            .mode(mode)
+            .submit_oneshot(&reactor)
+            .await?;
+
+        let mut file = File::new(reactor, file);
+
+        if self.direct {
+            file.alignment = Some(file.direct_alignment().await?);
+        }
+
+        Ok(file)
+    }
+
+    /// Translate the options into `open(2)` flags in the same way as the
+    /// standard library
+    fn flags(&self) -> Result<i32> {
+        let access = match (self.read, self.write, self.append) {
+            (true, false, false) => libc::O_RDONLY,
+            (false, true, false) => libc::O_WRONLY,
+            (true, true, false) => libc::O_RDWR,
+            (false, _, true) => libc::O_WRONLY | libc::O_APPEND,
+            (true, _, true) => libc::O_RDWR | libc::O_APPEND,
+            (false, false, false) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "opening requires read, write or append access",
+                ));
+            }
+        };
+
+        let contradictory = match (self.write, self.append) {
+            (true, false) => false,
+            (false, false) => self.truncate || self.create || self.create_new,
+            (_, true) => self.truncate && !self.create_new,
+        };
+
+        if contradictory {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "creating or truncating requires write access without appending",
+            ));
+        }
+
+        let creation = match (self.create, self.truncate, self.create_new) {
+            (false, false, false) => 0,
+            (true, false, false) => libc::O_CREAT,
+            (false, true, false) => libc::O_TRUNC,
+            (true, true, false) => libc::O_CREAT | libc::O_TRUNC,
+            (_, _, true) => libc::O_CREAT | libc::O_EXCL,
+        };
+
+        let direct = if self.direct { libc::O_DIRECT } else { 0 };
+
+        Ok(libc::O_CLOEXEC | access | creation | direct | (self.custom_flags & !libc::O_ACCMODE))
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// File reading and writing at explicit offsets through `io_uring`
+pub struct File {
+    reactor: Rc<Reactor>,
+    descriptor: ManuallyDrop<OwnedFd>,
+    alignment: Option<usize>,
+    closed: bool,
+}
+
+impl File {
+    const fn new(reactor: Rc<Reactor>, file: OwnedFd) -> Self {
+        Self {
+            reactor,
+            descriptor: ManuallyDrop::new(file),
+            alignment: None,
+            closed: false,
+        }
+    }
+
+    /// Open the file at the path for reading
+    ///
+    /// # Errors
+    ///
+    /// If opening fails
+    pub async fn open(reactor: Rc<Reactor>, path: impl AsRef<Path>) -> Result<Self> {
+        OpenOptions::new().read(true).open(reactor, path).await
+    }
+
+    /// Open the file at the path for writing, creating it if it doesn't exist
+    /// and truncating it otherwise
+    ///
+    /// # Errors
+    ///
+    /// If opening fails
+    pub async fn create(reactor: Rc<Reactor>, path: impl AsRef<Path>) -> Result<Self> {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(reactor, path)
+            .await
+    }
+
+    /// Options for opening files in other ways
+    pub const fn options() -> OpenOptions {
+        OpenOptions::new()
+    }
+
+    /// Use an already opened file, querying the alignment for direct I/O if
+    /// it was opened with `O_DIRECT`
+    ///
+    /// # Errors
+    ///
+    /// If the file's flags or alignment can't be queried
+    pub async fn from_std(reactor: Rc<Reactor>, file: std::fs::File) -> Result<Self> {
+        // SAFETY: the file is open and the command takes no argument
+        let flags = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETFL) };
+        if flags < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        let mut file = Self::new(reactor, file.into());
+
+        if flags & libc::O_DIRECT != 0 {
+            file.alignment = Some(file.direct_alignment().await?);
+        }
+
+        Ok(file)
+    }
+
+    /// Alignment of buffers, lengths and offsets required for files opened
+    /// with [`OpenOptions::direct`], which is enforced before submitting
+    #[must_use]
+    pub const fn alignment(&self) -> Option<usize> {
+        self.alignment
+    }
+
+    /// Read into the spare capacity of the buffer from the given offset,
+    /// where reading nothing means the end of the file was reached
+    ///
+    /// # Errors
+    ///
+    /// If the transfer isn't aligned for direct I/O or reading fails
+    pub async fn read_at<B: IoBuffer>(&self, buffer: B, offset: u64) -> Result<B> {
+        // transfers are limited to a gigabyte, which keeps aligned lengths
+        // aligned
+        self.check_alignment(
+            buffer.as_ptr().wrapping_add(buffer.len()),
+            buffer.capacity() - buffer.len(),
+            offset,
+        )?;
+
+        Ok(ReadAt::new(self.as_fd(), buffer, offset)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Fill the spare capacity of the buffer from the given offset
+    ///
+    /// # Errors
+    ///
+    /// If the transfer isn't aligned for direct I/O, reading fails or the end
+    /// of the file is reached first
+    pub async fn read_exact_at<B: IoBuffer>(&self, mut buffer: B, mut offset: u64) -> Result<B> {
+        while buffer.len() < buffer.capacity() {
+            let before = buffer.len();
+            buffer = self.read_at(buffer, offset).await?;
+
+            // direct reads only come up short at the end of the file, which
+            // would also leave the rest of the buffer unaligned
+            let amount = buffer.len() - before;
+            let short = self
+                .alignment
+                .is_some_and(|alignment| !amount.is_multiple_of(alignment));
+
+            if amount == 0 || short {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "failed to fill whole buffer",
+                ));
+            }
+
+            offset += amount as u64;
+        }
+
+        Ok(buffer)
+    }
+
+    /// Write the contents of the buffer at the given offset, returning how
+    /// much was written along with the buffer
+    ///
+    /// # Errors
+    ///
+    /// If the transfer isn't aligned for direct I/O or writing fails
+    pub async fn write_at<B: IoBuffer>(&self, buffer: B, offset: u64) -> Result<(usize, B)> {
+        self.write_from_at(buffer, 0, offset).await
+    }
+
+    /// Write all contents of the buffer at the given offset
+    ///
+    /// # Errors
+    ///
+    /// If the transfer isn't aligned for direct I/O or writing fails
+    pub async fn write_all_at<B: IoBuffer>(&self, mut buffer: B, mut offset: u64) -> Result<B> {
+        let mut written = 0;
+
+        while written < buffer.len() {
+            let (amount, returned) = self.write_from_at(buffer, written, offset).await?;
+            buffer = returned;
+
+            if amount == 0 {
+                return Err(Error::new(
+                    ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ));
+            }
+
+            written += amount;
+            offset += amount as u64;
+        }
+
+        Ok(buffer)
+    }
+
+    /// Write the contents of the buffer past the given position in it
+    async fn write_from_at<B: IoBuffer>(
+        &self,
+        buffer: B,
+        position: usize,
+        offset: u64,
+    ) -> Result<(usize, B)> {
+        self.check_alignment(
+            buffer.as_ptr().wrapping_add(position),
+            buffer.len() - position,
+            offset,
+        )?;
+
+        Ok(WriteAt::new(self.as_fd(), buffer, offset)
+            .position(position)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Reject transfers that the kernel can't perform with `O_DIRECT`
+    fn check_alignment(&self, start: *const u8, length: usize, offset: u64) -> Result<()> {
+        let Some(alignment) = self.alignment else {
+            return Ok(());
+        };
+
+        if start.addr().is_multiple_of(alignment)
+            && length.is_multiple_of(alignment)
+            && offset.is_multiple_of(alignment as u64)
+        {
+            return Ok(());
+        }
+
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "direct I/O requires buffers, lengths and offsets aligned to {alignment} bytes"
+            ),
+        ))
+    }
+
+    /// Flush contents and metadata to the storage device
+    ///
+    /// # Errors
+    ///
+    /// If syncing fails
+    pub async fn sync_all(&self) -> Result<()> {
+        Ok(Fsync::new(self.as_fd())
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Flush contents to the storage device, along with only the metadata
+    /// needed to read them back
+    ///
+    /// # Errors
+    ///
+    /// If syncing fails
+    pub async fn sync_data(&self) -> Result<()> {
+        Ok(Fsync::new(self.as_fd())
+            .data_only()
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Truncate or extend the file to the given size
+    ///
+    /// Requires Linux 6.9 or newer
+    ///
+    /// # Errors
+    ///
+    /// If truncating fails
+    pub async fn set_len(&self, size: u64) -> Result<()> {
+        Ok(Ftruncate::new(self.as_fd(), size)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Query the file's metadata through `IORING_OP_STATX`
+    ///
+    /// # Errors
+    ///
+    /// If querying fails
+    pub async fn metadata(&self) -> Result<Metadata> {
+        self.statx(libc::STATX_BASIC_STATS | libc::STATX_BTIME)
+            .await
+    }
+
+    async fn statx(&self, mask: u32) -> Result<Metadata> {
+        let statx = Statx::new(self.as_fd(), mask)
+            .submit_oneshot(&self.reactor)
+            .await?;
+
+        Ok(Metadata { statx })
+    }
+
+    /// Query the alignment the file system needs for direct I/O on the file,
+    /// falling back to a common block size when it doesn't report any
+    async fn direct_alignment(&self) -> Result<usize> {
+        let metadata = self.statx(libc::STATX_DIOALIGN).await?;
+        let statx = metadata.as_raw();
+
+        if statx.stx_mask & libc::STATX_DIOALIGN == 0 || statx.stx_dio_mem_align == 0 {
+            return Ok(DEFAULT_DIRECT_ALIGNMENT);
+        }
+
+        Ok(statx.stx_dio_mem_align.max(statx.stx_dio_offset_align) as usize)
+    }
+
+    /// Close the file through `IORING_OP_CLOSE`, reporting errors that are
+    /// otherwise ignored when dropping it
+    ///
+    /// # Errors
+    ///
+    /// If closing fails
+    pub async fn close(mut self) -> Result<()> {
+        self.closed = true;
+
+        // SAFETY: the file isn't used after this point, as it's marked closed
+        let file = unsafe { ManuallyDrop::take(&mut self.descriptor) };
+
+        Ok(uring_operation::Close::new(file)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+}
+
+impl AsFd for File {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.descriptor.as_fd()
+    }
+}
+
+impl AsRawFd for File {
+    fn as_raw_fd(&self) -> RawFd {
+        self.descriptor.as_raw_fd()
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        // closing already took over the file
+        if self.closed {
+            return;
+        }
+
+        // SAFETY: the file isn't used after this point
+        let file = unsafe { ManuallyDrop::take(&mut self.descriptor) };
+
+        let entry = Close::new(Fd(file.as_raw_fd())).build();
+
+        // SAFETY: the kernel takes over the file, which is otherwise closed
+        // synchronously when failing to submit
+        if unsafe { self.reactor.submit_detached(entry, ()) }.is_ok() {
+            let _ = file.into_raw_fd();
+        }
+    }
+}
+
+/// Metadata of a [`File`] as reported by `statx(2)`
+#[derive(Clone, Copy)]
+pub struct Metadata {
+    statx: libc::statx,
+}
+
+impl Metadata {
+    /// Size of the file in bytes
+    #[must_use]
+    pub const fn len(&self) -> u64 {
+        self.statx.stx_size
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.statx.stx_size == 0
+    }
+
+    #[must_use]
+    pub const fn is_file(&self) -> bool {
+        self.file_type() == libc::S_IFREG
+    }
+
+    #[must_use]
+    pub const fn is_dir(&self) -> bool {
+        self.file_type() == libc::S_IFDIR
+    }
+
+    #[must_use]
+    pub const fn is_symlink(&self) -> bool {
+        self.file_type() == libc::S_IFLNK
+    }
+
+    const fn file_type(&self) -> u32 {
+        self.statx.stx_mode as u32 & libc::S_IFMT
+    }
+
+    /// File type and permission bits
+    #[must_use]
+    pub const fn mode(&self) -> u32 {
+        self.statx.stx_mode as u32
+    }
+
+    /// # Errors
+    ///
+    /// If the file system didn't report the time
+    pub fn modified(&self) -> Result<SystemTime> {
+        self.time(libc::STATX_MTIME, self.statx.stx_mtime)
+    }
+
+    /// # Errors
+    ///
+    /// If the file system didn't report the time
+    pub fn accessed(&self) -> Result<SystemTime> {
+        self.time(libc::STATX_ATIME, self.statx.stx_atime)
+    }
+
+    /// # Errors
+    ///
+    /// If the file system didn't report the time
+    pub fn created(&self) -> Result<SystemTime> {
+        self.time(libc::STATX_BTIME, self.statx.stx_btime)
+    }
+
+    fn time(&self, mask: u32, time: libc::statx_timestamp) -> Result<SystemTime> {
+        if self.statx.stx_mask & mask == 0 {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "time not available on this file system",
+            ));
+        }
+
+        let seconds = Duration::from_secs(time.tv_sec.unsigned_abs());
+        let nanoseconds = Duration::from_nanos(time.tv_nsec.into());
+
+        let time = if time.tv_sec.is_negative() {
+            SystemTime::UNIX_EPOCH - seconds + nanoseconds
+        } else {
+            SystemTime::UNIX_EPOCH + seconds + nanoseconds
+        };
+
+        Ok(time)
+    }
+
+    /// Everything the kernel reported, for fields without an accessor
+    #[must_use]
+    pub const fn as_raw(&self) -> &libc::statx {
+        &self.statx
+    }
+}
+
+/// Fixed capacity heap memory with the alignment required by
+/// [`File::alignment`] for direct I/O
+pub struct AlignedBuffer {
+    memory: NonNull<u8>,
+    layout: Layout,
+    length: usize,
+}
+
+impl AlignedBuffer {
+    /// Allocate the given capacity at the given alignment
+    ///
+    /// # Panics
+    ///
+    /// If the capacity is zero, the alignment isn't a power of two or the
+    /// capacity overflows when rounded up to the alignment
+    #[must_use]
+    pub fn new(capacity: usize, alignment: usize) -> Self {
+        assert!(capacity > 0, "aligned buffer capacity must be non-zero");
+        let layout = Layout::from_size_align(capacity, alignment).expect("invalid buffer layout");
+
+        // SAFETY: the layout has a non-zero size
+        let memory = unsafe { std::alloc::alloc(layout) };
+        let Some(memory) = NonNull::new(memory) else {
+            std::alloc::handle_alloc_error(layout);
+        };
+
+        Self {
+            memory,
+            layout,
+            length: 0,
+        }
+    }
+
+    /// Append the data to the contents
+    ///
+    /// # Panics
+    ///
+    /// If the data doesn't fit into the remaining capacity
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        assert!(
+            data.len() <= self.layout.size() - self.length,
+            "data exceeds the buffer capacity"
+        );
+
+        // SAFETY: the range is in bounds and doesn't overlap with the data,
+        // which the borrow rules out
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                self.memory.as_ptr().add(self.length),
+                data.len(),
+            );
+        }
+
+        self.length += data.len();
+    }
+
+    /// Forget the contents while keeping the memory
+    pub const fn clear(&mut self) {
+        self.length = 0;
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: the length covers initialized memory
+        unsafe { std::slice::from_raw_parts(self.memory.as_ptr(), self.length) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the length covers initialized memory
+        unsafe { std::slice::from_raw_parts_mut(self.memory.as_ptr(), self.length) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        // SAFETY: allocated with the same layout
+        unsafe { std::alloc::dealloc(self.memory.as_ptr(), self.layout) };
+    }
+}
+
+// SAFETY: the memory is allocated once and never moves
+unsafe impl IoBuffer for AlignedBuffer {
+    fn as_ptr(&self) -> *const u8 {
+        self.memory.as_ptr()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.memory.as_ptr()
+    }
+
+    fn len(&self) -> usize {
+        self.length
+    }
+
+    fn capacity(&self) -> usize {
+        self.layout.size()
+    }
+
+    unsafe fn set_len(&mut self, length: usize) {
+        self.length = length;
+    }
+}
@@ -1,45 +1,116 @@
+pub mod fs;
+pub mod net;
+mod split;
+
 use std::{
-    io::{Error, ErrorKind, Result},
-    mem::MaybeUninit,
-    os::fd::{AsRawFd, OwnedFd},
+    io::{Error, ErrorKind, IoSlice, Result},
+    mem::{ManuallyDrop, MaybeUninit},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd, RawFd},
     pin::Pin,
     rc::Rc,
     task::{Context, Poll},
 };
 
 use io_uring::{
-    opcode::{AsyncCancel, Close, PollAdd, Shutdown, Write},
+    opcode::{self, AsyncCancel, Close, Read, Shutdown, Writev},
+    squeue::Flags,
     types::Fd,
 };
+use uring_operation::{Operation, OperationError, Poll as PollReadiness, Readiness};
 use uring_reactor::{OperationId, Reactor};
 
+pub use crate::split::{ReadHalf, ReuniteError, WriteHalf};
+
 /// Adapter to implement common IO traits backed by `io_uring`
+///
+/// Writes are collected in an internal buffer, so callers have to wait for
+/// [`PollIo::poll_flush`] or [`PollIo::poll_close`] before dropping the
+/// adapter. Dropping it cancels operations still in flight and discards
+/// buffered data the kernel didn't take yet, before closing the file in the
+/// background.
 pub struct PollIo {
     reactor: Rc<Reactor>,
-    file: OwnedFd,
+    file: ManuallyDrop<OwnedFd>,
     read: Option<OperationId>,
+    read_buffer: Option<ReadBuffer>,
     write: Option<OperationId>,
+    write_buffer: Option<WriteBuffer>,
+    write_capacity: usize,
+    cancel: Option<OperationId>,
     shutdown: Option<OperationId>,
     close: Option<OperationId>,
+    closed: bool,
 }
 
+/// Capacity of the write buffer unless configured otherwise
+const DEFAULT_WRITE_CAPACITY: usize = 8 * 1024;
+
 impl PollIo {
     pub const fn new(reactor: Rc<Reactor>, file: OwnedFd) -> Self {
         Self {
             reactor,
-            file,
+            file: ManuallyDrop::new(file),
             read: None,
+            read_buffer: None,
             write: None,
+            write_buffer: None,
+            write_capacity: DEFAULT_WRITE_CAPACITY,
+            cancel: None,
             shutdown: None,
             close: None,
+            closed: false,
         }
     }
 
+    /// Read through `IORING_OP_READ` into an internal buffer of the given
+    /// capacity that's copied from afterwards, instead of native reads
+    ///
+    /// # Panics
+    ///
+    /// If the capacity is zero or overflows the [`u32`] length field used by
+    /// `io_uring`
+    #[must_use]
+    pub fn with_read_buffer(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "read buffer capacity must be non-zero");
+        assert!(
+            u32::try_from(capacity).is_ok(),
+            "read buffer capacity too large"
+        );
+
+        self.read_buffer = Some(ReadBuffer {
+            storage: Box::new_uninit_slice(capacity),
+            start: 0,
+            end: 0,
+        });
+
+        self
+    }
+
+    /// Collect writes in an internal buffer of the given capacity instead of
+    /// the default of 8 KiB, which is allocated on the first write
+    ///
+    /// # Panics
+    ///
+    /// If the capacity is zero or overflows the [`u32`] length field used by
+    /// `io_uring`
+    #[must_use]
+    pub fn with_write_buffer(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "write buffer capacity must be non-zero");
+        assert!(
+            u32::try_from(capacity).is_ok(),
+            "write buffer capacity too large"
+        );
+
+        self.write_capacity = capacity;
+        self
+    }
+
     /// Attempt to read into the buffer
     ///
     /// # Note
     ///
-    /// This method uses a native `read(2)` call with `IORING_OP_POLL_ADD` when
+    /// Without an internal buffer set up through [`PollIo::with_read_buffer`]
+    /// this method uses a native `read(2)` call with `IORING_OP_POLL_ADD` when
     /// encountering `EAGAIN` due to otherwise resulting in aliasing of the
     /// buffer reference
     pub fn poll_read(
@@ -48,25 +119,41 @@ impl PollIo {
         buffer: &mut [MaybeUninit<u8>],
     ) -> Poll<Result<usize>> {
         let this = self.get_mut();
+        let file = this.file.as_raw_fd();
+
+        if this.closed {
+            return Poll::Ready(Err(closed()));
+        }
+
+        // like `std`, reading into nothing succeeds without touching the file,
+        // so that reading nothing otherwise only happens at the end of it
+        if buffer.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        if this.read_buffer.is_some() {
+            return this.poll_read_buffered(context, buffer);
+        }
 
         if let Some(handle) = this.read {
             let entry = std::task::ready!(this.reactor.drive_operation(handle, context));
             this.read = None;
 
             if entry.result().is_negative() {
-                return Poll::Ready(Err(Error::from_raw_os_error(-entry.result())));
+                let error = Error::from_raw_os_error(-entry.result());
+                return Poll::Ready(Err(failure(
+                    opcode::PollAdd::CODE,
+                    Some(file),
+                    Some(handle),
+                    error,
+                )));
             }
         }
 
         // SAFETY: valid pointer with correct length and file bound to live long enough
-        let result = usize::try_from(unsafe {
-            libc::read(
-                this.file.as_raw_fd(),
-                buffer.as_mut_ptr().cast(),
-                buffer.len(),
-            )
-        })
-        .map_err(|_| Error::last_os_error());
+        let result =
+            usize::try_from(unsafe { libc::read(file, buffer.as_mut_ptr().cast(), buffer.len()) })
+                .map_err(|_| Error::last_os_error());
 
         match result {
             Ok(amount) => Poll::Ready(Ok(amount)),
@@ -75,68 +162,239 @@ impl PollIo {
                 Poll::Pending
             }
             Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                let mut readiness = PollReadiness::new(this.file.as_fd(), Readiness::READABLE);
+                let entry =
+                    <PollReadiness as Operation>::build_submission(Pin::new(&mut readiness));
+
                 // SAFETY: file bound to live long enough
-                unsafe {
-                    this.reactor.submit_operation(
-                        PollAdd::new(Fd(this.file.as_raw_fd()), libc::POLLIN as _).build(),
-                        context,
-                    )
-                }
-                .map_or_else(
-                    |error| Poll::Ready(Err(error)),
+                unsafe { this.reactor.submit_operation(entry, context) }.map_or_else(
+                    |error| {
+                        Poll::Ready(Err(failure(opcode::PollAdd::CODE, Some(file), None, error)))
+                    },
                     |handle| {
                         this.read = Some(handle);
                         Poll::Pending
                     },
                 )
             }
+            // failures of the native read aren't attributed to any opcode
             Err(error) => Poll::Ready(Err(error)),
         }
     }
 
-    /// Attempt to write the buffer's contents
+    fn poll_read_buffered(
+        &mut self,
+        context: &mut Context,
+        buffer: &mut [MaybeUninit<u8>],
+    ) -> Poll<Result<usize>> {
+        let internal = std::task::ready!(self.poll_fill_buffered(context))?;
+        let amount = buffer.len().min(internal.end - internal.start);
+        buffer[..amount]
+            .copy_from_slice(&internal.storage[internal.start..internal.start + amount]);
+        internal.start += amount;
+
+        Poll::Ready(Ok(amount))
+    }
+
+    /// Attempt to fill the internal read buffer and return its contents,
+    /// where an empty buffer means the end of the file was reached
+    ///
+    /// # Errors
+    ///
+    /// If no internal buffer was set up through [`PollIo::with_read_buffer`]
+    pub fn poll_fill_buf(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<&[u8]>> {
+        let internal = std::task::ready!(self.get_mut().poll_fill_buffered(context))?;
+        let filled = &internal.storage[internal.start..internal.end];
+
+        // SAFETY: the range was initialized by the kernel
+        Poll::Ready(Ok(unsafe { &*(std::ptr::from_ref(filled) as *const [u8]) }))
+    }
+
+    /// Mark the given amount of bytes from [`PollIo::poll_fill_buf`] as read
     ///
     /// # Panics
     ///
-    /// If the buffer overflows the [`u32`] length field used by `io_uring`
+    /// If the amount exceeds the filled contents
+    pub fn consume(self: Pin<&mut Self>, amount: usize) {
+        let Some(internal) = self.get_mut().read_buffer.as_mut() else {
+            assert!(amount == 0, "consumed more than was filled");
+            return;
+        };
+
+        assert!(
+            amount <= internal.end - internal.start,
+            "consumed more than was filled"
+        );
+
+        internal.start += amount;
+    }
+
+    /// Read into the internal buffer once everything was handed out
+    fn poll_fill_buffered(&mut self, context: &mut Context) -> Poll<Result<&mut ReadBuffer>> {
+        let file = self.file.as_raw_fd();
+        if self.closed {
+            return Poll::Ready(Err(closed()));
+        }
+
+        let Some(internal) = self.read_buffer.as_mut() else {
+            return Poll::Ready(Err(Error::new(
+                ErrorKind::Unsupported,
+                "filling requires an internal read buffer",
+            )));
+        };
+
+        if internal.start != internal.end {
+            return Poll::Ready(Ok(internal));
+        }
+
+        if let Some(handle) = self.read {
+            let entry = std::task::ready!(self.reactor.drive_operation(handle, context));
+            self.read = None;
+
+            let Ok(amount) = usize::try_from(entry.result()) else {
+                let error = Error::from_raw_os_error(-entry.result());
+                return Poll::Ready(Err(failure(
+                    opcode::Read::CODE,
+                    Some(file),
+                    Some(handle),
+                    error,
+                )));
+            };
+
+            internal.start = 0;
+            internal.end = amount;
+
+            return Poll::Ready(Ok(internal));
+        }
+
+        let entry = Read::new(
+            Fd(file),
+            internal.storage.as_mut_ptr().cast(),
+            internal.storage.len().try_into().unwrap(),
+        )
+        .offset(u64::MAX)
+        .build();
+
+        // SAFETY: the storage is owned and only freed once the read completed,
+        // while the file is bound to live long enough
+        unsafe { self.reactor.submit_operation(entry, context) }.map_or_else(
+            |error| Poll::Ready(Err(failure(opcode::Read::CODE, Some(file), None, error))),
+            |handle| {
+                self.read = Some(handle);
+                Poll::Pending
+            },
+        )
+    }
+
+    /// Attempt to write the buffer's contents
+    ///
+    /// # Note
+    ///
+    /// The contents are copied into an internal buffer that's only submitted
+    /// through `IORING_OP_WRITEV` once full or when flushing, so that small
+    /// writes get coalesced and the caller's buffer isn't referenced by the
+    /// kernel
     pub fn poll_write(
         self: Pin<&mut Self>,
         context: &mut Context,
         buffer: &[u8],
+    ) -> Poll<Result<usize>> {
+        self.poll_write_vectored(context, &[IoSlice::new(buffer)])
+    }
+
+    /// Attempt to write the contents of multiple buffers, in the same way as
+    /// [`PollIo::poll_write`]
+    pub fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffers: &[IoSlice],
     ) -> Poll<Result<usize>> {
         let this = self.get_mut();
 
-        if let Some(handle) = this.write {
-            let entry = std::task::ready!(this.reactor.drive_operation(handle, context));
-            this.write = None;
+        if this.closed {
+            return Poll::Ready(Err(closed()));
+        }
 
-            return Poll::Ready(
-                entry
-                    .result()
-                    .try_into()
-                    .map_err(|_| Error::from_raw_os_error(-entry.result())),
-            );
+        if buffers.iter().all(|buffer| buffer.is_empty()) {
+            return Poll::Ready(Ok(0));
         }
 
-        // SAFETY: valid pointer with correct length and file bound to live long enough
-        unsafe {
-            this.reactor.submit_operation(
-                Write::new(
-                    Fd(this.file.as_raw_fd()),
-                    buffer.as_ptr(),
-                    buffer.len().try_into().unwrap(),
-                )
-                .build(),
-                context,
-            )
+        if this.write_buffer.as_ref().is_some_and(WriteBuffer::is_full) {
+            std::task::ready!(this.poll_flush_buffered(context))?;
+        }
+
+        let capacity = this.write_capacity;
+        let internal = this
+            .write_buffer
+            .get_or_insert_with(|| WriteBuffer::new(capacity));
+
+        let mut amount = 0;
+        for buffer in buffers {
+            let pushed = internal.push(buffer);
+            amount += pushed;
+
+            if pushed < buffer.len() {
+                break;
+            }
+        }
+
+        Poll::Ready(Ok(amount))
+    }
+
+    /// Attempt to write all buffered contents, which is only ready once the
+    /// kernel accepted every byte
+    pub fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        self.get_mut().poll_flush_buffered(context)
+    }
+
+    fn poll_flush_buffered(&mut self, context: &mut Context) -> Poll<Result<()>> {
+        let file = self.file.as_raw_fd();
+        if self.closed {
+            return Poll::Ready(Err(closed()));
+        }
+
+        let Some(internal) = self.write_buffer.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+
+        loop {
+            if let Some(handle) = self.write {
+                let entry = std::task::ready!(self.reactor.drive_operation(handle, context));
+                self.write = None;
+
+                let amount = match usize::try_from(entry.result()) {
+                    Ok(0) => Err(Error::from(ErrorKind::WriteZero)),
+                    Ok(amount) => Ok(amount),
+                    Err(_) => Err(Error::from_raw_os_error(-entry.result())),
+                }
+                .map_err(|error| failure(opcode::Writev::CODE, Some(file), Some(handle), error))?;
+
+                internal.consume(amount);
+            }
+
+            if internal.length == 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            let vectors = internal.pending();
+            let entry = Writev::new(Fd(file), internal.vectors.as_ptr(), vectors)
+                .offset(u64::MAX)
+                .build();
+
+            // SAFETY: the storage and vectors are owned and only freed once
+            // the write completed, while the file is bound to live long enough
+            match unsafe { self.reactor.submit_operation(entry, context) } {
+                Ok(handle) => self.write = Some(handle),
+                Err(error) => {
+                    return Poll::Ready(Err(failure(
+                        opcode::Writev::CODE,
+                        Some(file),
+                        None,
+                        error,
+                    )));
+                }
+            }
         }
-        .map_or_else(
-            |error| Poll::Ready(Err(error)),
-            |handle| {
-                this.write = Some(handle);
-                Poll::Pending
-            },
-        )
     }
 
     /// Attempt to shutdown the socket
@@ -147,90 +405,140 @@ impl PollIo {
     ///
     /// # Note
     ///
-    /// This method cancels read and write operations if they haven't completed
+    /// This method flushes buffered writes first and cancels the read
+    /// operation if it hasn't completed
     pub fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
-        let this = self.get_mut();
-        let mut dummy = None;
-        let remaining = if this.read.is_some() {
-            &mut this.read
-        } else if this.write.is_none() {
-            &mut this.write
-        } else {
-            &mut dummy
-        };
+        self.get_mut().poll_shutdown_with(context, true)
+    }
+
+    fn poll_shutdown_with(&mut self, context: &mut Context, cancel_read: bool) -> Poll<Result<()>> {
+        let file = self.file.as_raw_fd();
+        if self.closed {
+            return Poll::Ready(Err(closed()));
+        }
+
+        if self.shutdown.is_none() {
+            std::task::ready!(self.poll_flush_buffered(context))?;
+        }
 
-        match (remaining, this.shutdown) {
-            // There is a operation that we need to cancel
-            (Some(handle), None) => {
+        if let Some(read) = self.read.filter(|_| cancel_read) {
+            if self.cancel.is_none() {
                 // SAFETY: we don't set any parameters that can get invalidated
-                unsafe {
-                    this.reactor.submit_operation(
-                        AsyncCancel::new(handle.as_raw().try_into().unwrap()).build(),
+                let submitted = unsafe {
+                    self.reactor.submit_operation(
+                        AsyncCancel::new(read.as_raw().try_into().unwrap()).build(),
                         context,
                     )
+                };
+
+                match submitted {
+                    Ok(handle) => self.cancel = Some(handle),
+                    Err(error) => {
+                        return Poll::Ready(Err(failure(
+                            opcode::AsyncCancel::CODE,
+                            None,
+                            None,
+                            error,
+                        )))
+                    }
                 }
-                .map_or_else(
-                    |error| Poll::Ready(Err(error)),
-                    |handle| {
-                        this.shutdown = Some(handle);
-                        Poll::Pending
-                    },
-                )
             }
-            // We're waiting for a cancellation
-            (slot @ Some(_), Some(handle)) => {
-                let entry = std::task::ready!(this.reactor.drive_operation(handle, context));
-                this.shutdown = None;
-                *slot = None;
-
-                if entry.result().is_negative() {
-                    Poll::Ready(Err(Error::from_raw_os_error(-entry.result())))
-                } else {
-                    context.waker().wake_by_ref();
-                    Poll::Pending
-                }
+
+            // the read may complete by itself before the cancellation gets to
+            // it, so it's only over with its own completion
+            let entry = std::task::ready!(self.reactor.drive_operation(read, context));
+            self.read = None;
+
+            if let (Some(internal), Ok(amount)) =
+                (self.read_buffer.as_mut(), usize::try_from(entry.result()))
+            {
+                internal.start = 0;
+                internal.end = amount;
             }
-            // Ready to shutdown
-            (None, None) => {
-                // SAFETY: file bound to live long enough
-                unsafe {
-                    this.reactor.submit_operation(
-                        Shutdown::new(Fd(this.file.as_raw_fd()), libc::SHUT_WR).build(),
-                        context,
-                    )
-                }
-                .map_or_else(
-                    |error| Poll::Ready(Err(error)),
-                    |handle| {
-                        this.shutdown = Some(handle);
-                        Poll::Pending
-                    },
-                )
+        }
+
+        if let Some(handle) = self.cancel {
+            let entry = std::task::ready!(self.reactor.drive_operation(handle, context));
+            self.cancel = None;
+
+            // reads that completed by themselves can't be found anymore or
+            // already ran to the end
+            if entry.result().is_negative()
+                && ![libc::ENOENT, libc::EALREADY].contains(&-entry.result())
+            {
+                let error = Error::from_raw_os_error(-entry.result());
+                return Poll::Ready(Err(failure(
+                    opcode::AsyncCancel::CODE,
+                    None,
+                    Some(handle),
+                    error,
+                )));
             }
-            // We're waiting for the shutdown
-            (None, Some(handle)) => {
-                let entry = std::task::ready!(this.reactor.drive_operation(handle, context));
-                this.shutdown = None;
-
-                if entry.result().is_negative() {
-                    Poll::Ready(Err(Error::from_raw_os_error(-entry.result())))
-                } else {
-                    Poll::Ready(Ok(()))
-                }
+        }
+
+        let Some(handle) = self.shutdown else {
+            // SAFETY: file bound to live long enough
+            return unsafe {
+                self.reactor
+                    .submit_operation(Shutdown::new(Fd(file), libc::SHUT_WR).build(), context)
             }
+            .map_or_else(
+                |error| {
+                    Poll::Ready(Err(failure(
+                        opcode::Shutdown::CODE,
+                        Some(file),
+                        None,
+                        error,
+                    )))
+                },
+                |handle| {
+                    self.shutdown = Some(handle);
+                    Poll::Pending
+                },
+            );
+        };
+
+        let entry = std::task::ready!(self.reactor.drive_operation(handle, context));
+        self.shutdown = None;
+
+        if entry.result().is_negative() {
+            let error = Error::from_raw_os_error(-entry.result());
+            return Poll::Ready(Err(failure(
+                opcode::Shutdown::CODE,
+                Some(file),
+                Some(handle),
+                error,
+            )));
         }
+
+        Poll::Ready(Ok(()))
     }
 
-    /// Attempt to close the file
+    /// Attempt to close the file after flushing buffered writes
     pub fn poll_close(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
         let this = self.get_mut();
+        let file = this.file.as_raw_fd();
+
+        if this.close.is_none() {
+            if this.closed {
+                return Poll::Ready(Ok(()));
+            }
+
+            std::task::ready!(this.poll_flush_buffered(context))?;
+        }
 
         if let Some(handle) = this.close {
             let entry = std::task::ready!(this.reactor.drive_operation(handle, context));
             this.close = None;
 
             if entry.result().is_negative() {
-                return Poll::Ready(Err(Error::from_raw_os_error(-entry.result())));
+                let error = Error::from_raw_os_error(-entry.result());
+                return Poll::Ready(Err(failure(
+                    opcode::Close::CODE,
+                    Some(file),
+                    Some(handle),
+                    error,
+                )));
             }
 
             return Poll::Ready(Ok(()));
@@ -239,113 +547,445 @@ impl PollIo {
         // SAFETY: file bound to live long enough
         unsafe {
             this.reactor
-                .submit_operation(Close::new(Fd(this.file.as_raw_fd())).build(), context)
+                .submit_operation(Close::new(Fd(file)).build(), context)
         }
         .map_or_else(
-            |error| Poll::Ready(Err(error)),
+            |error| Poll::Ready(Err(failure(opcode::Close::CODE, Some(file), None, error))),
             |handle| {
                 this.close = Some(handle);
+                this.closed = true;
                 Poll::Pending
             },
         )
     }
 }
 
+impl AsFd for PollIo {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl AsRawFd for PollIo {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+/// Failure of using the file after [`PollIo::poll_close`], whose descriptor
+/// may already refer to another file
+fn closed() -> Error {
+    Error::from_raw_os_error(libc::EBADF)
+}
+
+/// Attach the failed operation and file to an error
+fn failure(opcode: u8, file: Option<RawFd>, handle: Option<OperationId>, error: Error) -> Error {
+    OperationError::new(opcode, file, handle, error).into()
+}
+
 impl Drop for PollIo {
-    fn drop(&mut self) {}
+    fn drop(&mut self) {
+        // SAFETY: the file isn't used after this point
+        let file = unsafe { ManuallyDrop::take(&mut self.file) };
+
+        // the kernel may still write into the buffer of an in-flight read
+        // and read from the buffer of an in-flight write, while operations
+        // that already completed release their slot right away
+        let reactor = &self.reactor;
+        let in_flight: Vec<_> = [
+            self.read
+                .filter(|&handle| reactor.detach_operation(handle, self.read_buffer.take())),
+            self.write
+                .filter(|&handle| reactor.detach_operation(handle, self.write_buffer.take())),
+            self.shutdown
+                .filter(|&handle| reactor.detach_operation(handle, ())),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        for handle in [self.cancel, self.close].into_iter().flatten() {
+            reactor.detach_operation(handle, ());
+        }
+
+        // cancellations are linked with the final close so that it only runs
+        // once they completed, which requires room for the whole chain as an
+        // unterminated link would chain into unrelated submissions
+        let linked = !self.closed
+            && self
+                .reactor
+                .reserve_submissions(in_flight.len() + 1)
+                .is_ok();
+
+        // only operations that are still in flight keep their slot, so the
+        // cancellations can't hit another operation
+        for handle in in_flight {
+            let mut entry = AsyncCancel::new(handle.as_raw().try_into().unwrap()).build();
+            if linked {
+                entry = entry.flags(Flags::IO_HARDLINK);
+            }
+
+            // SAFETY: we don't set any parameters that can get invalidated,
+            // while failing to submit lets the operation complete by itself
+            drop(unsafe { self.reactor.submit_detached(entry, ()) });
+        }
+
+        if self.closed {
+            let _ = file.into_raw_fd();
+            return;
+        }
+
+        let entry = Close::new(Fd(file.as_raw_fd())).build();
+
+        // SAFETY: the kernel takes over the file, which is otherwise closed
+        // synchronously when failing to submit
+        if unsafe { self.reactor.submit_detached(entry, ()) }.is_ok() {
+            let _ = file.into_raw_fd();
+        }
+    }
+}
+
+/// Internal buffer filled by completion-based reads, with the initialized
+/// range that wasn't handed out yet
+struct ReadBuffer {
+    storage: Box<[MaybeUninit<u8>]>,
+    start: usize,
+    end: usize,
+}
+
+/// Internal ring buffer collecting writes, with the range that the kernel
+/// didn't accept yet which may wrap around
+struct WriteBuffer {
+    storage: Box<[u8]>,
+    vectors: Box<[libc::iovec; 2]>,
+    start: usize,
+    length: usize,
+}
+
+impl WriteBuffer {
+    fn new(capacity: usize) -> Self {
+        let empty = libc::iovec {
+            iov_base: std::ptr::null_mut(),
+            iov_len: 0,
+        };
+
+        Self {
+            storage: vec![0; capacity].into_boxed_slice(),
+            vectors: Box::new([empty; 2]),
+            start: 0,
+            length: 0,
+        }
+    }
+
+    const fn is_full(&self) -> bool {
+        self.length == self.storage.len()
+    }
+
+    /// Copy as much of the data as fits behind the pending range
+    fn push(&mut self, data: &[u8]) -> usize {
+        let capacity = self.storage.len();
+        let end = (self.start + self.length) % capacity;
+        let amount = data.len().min(capacity - self.length);
+        let first = amount.min(capacity - end);
+        let storage = self.storage.as_mut_ptr();
+
+        // SAFETY: both ranges are in bounds and outside of the pending range,
+        // which the kernel may be reading from without us creating references
+        // to it
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), storage.add(end), first);
+            std::ptr::copy_nonoverlapping(data.as_ptr().add(first), storage, amount - first);
+        }
+
+        self.length += amount;
+        amount
+    }
+
+    /// Describe the pending range through the vectors, returning how many of
+    /// them are used
+    fn pending(&mut self) -> u32 {
+        let capacity = self.storage.len();
+        let first = self.length.min(capacity - self.start);
+        let storage = self.storage.as_mut_ptr();
+
+        // SAFETY: both ranges are in bounds
+        unsafe {
+            self.vectors[0] = libc::iovec {
+                iov_base: storage.add(self.start).cast(),
+                iov_len: first,
+            };
+            self.vectors[1] = libc::iovec {
+                iov_base: storage.cast(),
+                iov_len: self.length - first,
+            };
+        }
+
+        if first == self.length {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Drop the amount of bytes accepted by the kernel from the pending range
+    const fn consume(&mut self, amount: usize) {
+        self.start = (self.start + amount) % self.storage.len();
+        self.length -= amount;
+
+        if self.length == 0 {
+            self.start = 0;
+        }
+    }
 }
 
 #[cfg(feature = "tokio-io")]
 mod tokio_io {
     use std::{
-        io::Result,
+        io::{IoSlice, Result},
         pin::Pin,
         task::{Context, Poll},
     };
 
     use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
-    use crate::PollIo;
-
-    impl AsyncRead for PollIo {
-        fn poll_read(
-            self: Pin<&mut Self>,
-            context: &mut Context,
-            buffer: &mut ReadBuf,
-        ) -> Poll<std::io::Result<()>> {
-            // SAFETY: doesn't uninitialize memory and correctly returns bytes written
-            unsafe {
-                self.poll_read(context, buffer.unfilled_mut())
-                    .map(|result| {
-                        result.map(|amount| {
-                            buffer.assume_init(amount);
-                            buffer.advance(amount);
-                        })
-                    })
+    use crate::{
+        net::{TcpStream, UnixStream},
+        PollIo,
+        ReadHalf,
+        WriteHalf,
+    };
+
+    macro_rules! impl_read {
+        ($type:ty) => {
+            impl AsyncRead for $type {
+                fn poll_read(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    buffer: &mut ReadBuf,
+                ) -> Poll<std::io::Result<()>> {
+                    // SAFETY: doesn't uninitialize memory and correctly returns bytes written
+                    unsafe {
+                        self.poll_read(context, buffer.unfilled_mut())
+                            .map(|result| {
+                                result.map(|amount| {
+                                    buffer.assume_init(amount);
+                                    buffer.advance(amount);
+                                })
+                            })
+                    }
+                }
             }
-        }
+        };
     }
 
-    impl AsyncWrite for PollIo {
-        fn poll_write(
-            self: Pin<&mut Self>,
-            context: &mut Context,
-            buffer: &[u8],
-        ) -> Poll<Result<usize>> {
-            self.poll_write(context, buffer)
-        }
+    macro_rules! impl_write {
+        ($type:ty) => {
+            impl AsyncWrite for $type {
+                fn poll_write(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    buffer: &[u8],
+                ) -> Poll<Result<usize>> {
+                    self.poll_write(context, buffer)
+                }
 
-        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<()>> {
-            Poll::Ready(Ok(()))
-        }
+                fn poll_write_vectored(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    buffers: &[IoSlice],
+                ) -> Poll<Result<usize>> {
+                    self.poll_write_vectored(context, buffers)
+                }
 
-        fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
-            self.poll_shutdown(context)
-        }
+                fn is_write_vectored(&self) -> bool {
+                    true
+                }
+
+                fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+                    self.poll_flush(context)
+                }
+
+                fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+                    self.poll_shutdown(context)
+                }
+            }
+        };
     }
+
+    impl_read!(PollIo);
+    impl_read!(ReadHalf);
+    impl_read!(TcpStream);
+    impl_read!(UnixStream);
+    impl_write!(PollIo);
+    impl_write!(WriteHalf);
+    impl_write!(TcpStream);
+    impl_write!(UnixStream);
 }
 
 #[cfg(feature = "hyper-io")]
 mod hyper_io {
     use std::{
-        io::Result,
+        io::{IoSlice, Result},
         pin::Pin,
         task::{Context, Poll},
     };
 
     use hyper::rt::{Read, ReadBufCursor, Write};
 
-    use crate::PollIo;
-
-    impl Read for PollIo {
-        fn poll_read(
-            self: Pin<&mut Self>,
-            context: &mut Context,
-            mut buffer: ReadBufCursor,
-        ) -> Poll<Result<()>> {
-            // SAFETY: doesn't uninitialize memory and correctly advances
-            unsafe {
-                self.poll_read(context, buffer.as_mut())
-                    .map(|result| result.map(|amount| buffer.advance(amount)))
+    use crate::{
+        net::{TcpStream, UnixStream},
+        PollIo,
+        ReadHalf,
+        WriteHalf,
+    };
+
+    macro_rules! impl_read {
+        ($type:ty) => {
+            impl Read for $type {
+                fn poll_read(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    mut buffer: ReadBufCursor,
+                ) -> Poll<Result<()>> {
+                    // SAFETY: doesn't uninitialize memory and correctly advances
+                    unsafe {
+                        self.poll_read(context, buffer.as_mut())
+                            .map(|result| result.map(|amount| buffer.advance(amount)))
+                    }
+                }
             }
-        }
+        };
     }
 
-    impl Write for PollIo {
-        fn poll_write(
-            self: Pin<&mut Self>,
-            context: &mut Context,
-            buffer: &[u8],
-        ) -> Poll<Result<usize>> {
-            self.poll_write(context, buffer)
-        }
+    macro_rules! impl_write {
+        ($type:ty) => {
+            impl Write for $type {
+                fn poll_write(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    buffer: &[u8],
+                ) -> Poll<Result<usize>> {
+                    self.poll_write(context, buffer)
+                }
+
+                fn poll_write_vectored(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    buffers: &[IoSlice],
+                ) -> Poll<Result<usize>> {
+                    self.poll_write_vectored(context, buffers)
+                }
+
+                fn is_write_vectored(&self) -> bool {
+                    true
+                }
+
+                fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+                    self.poll_flush(context)
+                }
+
+                fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+                    self.poll_shutdown(context)
+                }
+            }
+        };
+    }
+
+    impl_read!(PollIo);
+    impl_read!(ReadHalf);
+    impl_read!(TcpStream);
+    impl_read!(UnixStream);
+    impl_write!(PollIo);
+    impl_write!(WriteHalf);
+    impl_write!(TcpStream);
+    impl_write!(UnixStream);
+}
+
+#[cfg(feature = "futures-io")]
+mod futures_io {
+    use std::{
+        io::{IoSlice, Result},
+        mem::MaybeUninit,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite};
+
+    use crate::{
+        net::{TcpStream, UnixStream},
+        PollIo,
+        ReadHalf,
+        WriteHalf,
+    };
+
+    macro_rules! impl_read {
+        ($type:ty) => {
+            impl AsyncRead for $type {
+                fn poll_read(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    buffer: &mut [u8],
+                ) -> Poll<Result<usize>> {
+                    // SAFETY: doesn't uninitialize memory
+                    let buffer =
+                        unsafe { &mut *(std::ptr::from_mut(buffer) as *mut [MaybeUninit<u8>]) };
+
+                    self.poll_read(context, buffer)
+                }
+            }
+        };
+    }
+
+    macro_rules! impl_write {
+        ($type:ty, $close:ident) => {
+            impl AsyncWrite for $type {
+                fn poll_write(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    buffer: &[u8],
+                ) -> Poll<Result<usize>> {
+                    self.poll_write(context, buffer)
+                }
+
+                fn poll_write_vectored(
+                    self: Pin<&mut Self>,
+                    context: &mut Context,
+                    buffers: &[IoSlice],
+                ) -> Poll<Result<usize>> {
+                    self.poll_write_vectored(context, buffers)
+                }
+
+                fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+                    self.poll_flush(context)
+                }
+
+                fn poll_close(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+                    self.$close(context)
+                }
+            }
+        };
+    }
 
-        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<()>> {
-            Poll::Ready(Ok(()))
+    impl_read!(PollIo);
+    impl_read!(ReadHalf);
+    impl_read!(TcpStream);
+    impl_read!(UnixStream);
+    impl_write!(PollIo, poll_close);
+    impl_write!(TcpStream, poll_close);
+    impl_write!(UnixStream, poll_close);
+    // the file stays open for the reading half, so only writing is shut down
+    impl_write!(WriteHalf, poll_shutdown);
+
+    /// Only supported with an internal read buffer set up through
+    /// [`PollIo::with_read_buffer`], failing otherwise
+    impl AsyncBufRead for PollIo {
+        fn poll_fill_buf(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<&[u8]>> {
+            self.poll_fill_buf(context)
         }
 
-        fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
-            self.poll_shutdown(context)
+        fn consume(self: Pin<&mut Self>, amount: usize) {
+            self.consume(amount);
         }
     }
 }
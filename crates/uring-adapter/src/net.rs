@@ -0,0 +1,29 @@
+//! Sockets tied to a [`Reactor`](uring_reactor::Reactor) for the common cases
+//! that otherwise require assembling sockets, operations and adapters by hand
+
+mod tcp;
+mod udp;
+mod unix;
+
+use std::{
+    io::{Error, ErrorKind, Result},
+    net::SocketAddr,
+};
+
+use socket2::SockAddr;
+
+pub use self::{
+    tcp::{Incoming, TcpListener, TcpStream},
+    udp::{RecvMany, UdpSocket},
+    unix::{abstract_address, UnixDatagram, UnixListener, UnixStream},
+};
+
+/// Maximum length of the queue of pending connections
+const BACKLOG: libc::c_int = 1024;
+
+/// Convert an address reported for an internet socket
+fn internet_address(address: &SockAddr) -> Result<SocketAddr> {
+    address
+        .as_socket()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "not an internet address"))
+}
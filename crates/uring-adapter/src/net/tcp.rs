@@ -0,0 +1,279 @@
+use std::{
+    io::{IoSlice, Result},
+    mem::MaybeUninit,
+    net::SocketAddr,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd},
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use socket2::{Domain, Protocol, SockAddr, SockRef, Socket, Type};
+use uring_operation::{Accept, AcceptMulti, Connect, Multishot, Operation};
+use uring_reactor::Reactor;
+
+use crate::{
+    net::{internet_address, BACKLOG},
+    PollIo,
+    ReadHalf,
+    WriteHalf,
+};
+
+/// TCP socket accepting connections through `io_uring`
+pub struct TcpListener {
+    reactor: Rc<Reactor>,
+    socket: OwnedFd,
+}
+
+impl TcpListener {
+    /// Create a listener bound to the given address, with address reuse
+    /// enabled
+    ///
+    /// # Errors
+    ///
+    /// If creating, binding or listening on the socket fails
+    pub fn bind(reactor: Rc<Reactor>, address: SocketAddr) -> Result<Self> {
+        let address = SockAddr::from(address);
+        let socket = Socket::new(
+            address.domain(),
+            Type::STREAM.nonblocking(),
+            Some(Protocol::TCP),
+        )?;
+
+        socket.set_reuse_address(true)?;
+        socket.bind(&address)?;
+        socket.listen(BACKLOG)?;
+
+        Ok(Self {
+            reactor,
+            socket: socket.into(),
+        })
+    }
+
+    /// Use an already listening socket, such as one with port reuse enabled
+    ///
+    /// # Errors
+    ///
+    /// If making the socket non-blocking fails
+    pub fn from_std(reactor: Rc<Reactor>, listener: std::net::TcpListener) -> Result<Self> {
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            reactor,
+            socket: listener.into(),
+        })
+    }
+
+    /// Accept a single connection
+    ///
+    /// # Errors
+    ///
+    /// If accepting fails
+    pub async fn accept(&self) -> Result<(TcpStream, SocketAddr)> {
+        let (socket, address) = Accept::new(self.socket.as_fd())
+            .non_blocking_socket()
+            .close_socket_on_exec()
+            .submit_oneshot(&self.reactor)
+            .await?;
+
+        Ok((
+            TcpStream::from_socket(self.reactor.clone(), socket),
+            internet_address(&address)?,
+        ))
+    }
+
+    /// Accept connections continuously from a single multishot submission,
+    /// without addresses for peers that disconnected before they were queried
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming {
+            reactor: &self.reactor,
+            accept: Accept::new(self.socket.as_fd())
+                .non_blocking_socket()
+                .close_socket_on_exec()
+                .multishot()
+                .submit_multishot(&self.reactor),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        internet_address(&SockRef::from(&self.socket).local_addr()?)
+    }
+}
+
+impl AsFd for TcpListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+impl AsRawFd for TcpListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// Stream of connections created by [`TcpListener::incoming`]
+    #[must_use]
+    pub struct Incoming<'a> {
+        reactor: &'a Rc<Reactor>,
+        #[pin]
+        accept: Multishot<'a, AcceptMulti<'a>>,
+    }
+}
+
+impl Stream for Incoming<'_> {
+    type Item = Result<(TcpStream, Option<SocketAddr>)>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        this.accept.poll_next(context).map(|item| {
+            item.map(|result| {
+                let (socket, address) = result?;
+
+                Ok((
+                    TcpStream::from_socket(this.reactor.clone(), socket),
+                    address.and_then(|address| internet_address(&address).ok()),
+                ))
+            })
+        })
+    }
+}
+
+/// TCP connection reading and writing through `io_uring`
+///
+/// Writes are buffered, so they have to be flushed or the connection closed
+/// before dropping it to not lose any data, see [`PollIo`]
+pub struct TcpStream {
+    io: PollIo,
+}
+
+impl TcpStream {
+    const fn from_socket(reactor: Rc<Reactor>, socket: OwnedFd) -> Self {
+        Self {
+            io: PollIo::new(reactor, socket),
+        }
+    }
+
+    /// Open a connection to the given address
+    ///
+    /// # Errors
+    ///
+    /// If creating the socket or connecting fails
+    pub async fn connect(reactor: Rc<Reactor>, address: SocketAddr) -> Result<Self> {
+        let socket = Socket::new(
+            Domain::for_address(address),
+            Type::STREAM.nonblocking(),
+            Some(Protocol::TCP),
+        )?;
+
+        Connect::new(socket.as_fd(), SockAddr::from(address))
+            .submit_oneshot(&reactor)
+            .await?;
+
+        Ok(Self::from_socket(reactor, socket.into()))
+    }
+
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        internet_address(&SockRef::from(&self.io).local_addr()?)
+    }
+
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn peer_addr(&self) -> Result<SocketAddr> {
+        internet_address(&SockRef::from(&self.io).peer_addr()?)
+    }
+
+    /// Disable Nagle's algorithm so that small segments are sent right away
+    ///
+    /// # Errors
+    ///
+    /// If setting the option fails
+    pub fn set_nodelay(&self, nodelay: bool) -> Result<()> {
+        SockRef::from(&self.io).set_nodelay(nodelay)
+    }
+
+    /// # Errors
+    ///
+    /// If querying the option fails
+    pub fn nodelay(&self) -> Result<bool> {
+        SockRef::from(&self.io).nodelay()
+    }
+
+    /// Split into halves that can be used independently, see
+    /// [`PollIo::into_split`]
+    #[must_use]
+    pub fn into_split(self) -> (ReadHalf, WriteHalf) {
+        self.io.into_split()
+    }
+
+    /// Attempt to read into the buffer, see [`PollIo::poll_read`]
+    pub fn poll_read(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffer: &mut [MaybeUninit<u8>],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_read(context, buffer)
+    }
+
+    /// Attempt to write the buffer's contents, see [`PollIo::poll_write`]
+    pub fn poll_write(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffer: &[u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(context, buffer)
+    }
+
+    /// Attempt to write the contents of multiple buffers, see
+    /// [`PollIo::poll_write_vectored`]
+    pub fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffers: &[IoSlice],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(context, buffers)
+    }
+
+    /// Attempt to write all buffered contents, see [`PollIo::poll_flush`]
+    pub fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(context)
+    }
+
+    /// Attempt to shutdown the socket, see [`PollIo::poll_shutdown`]
+    pub fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(context)
+    }
+
+    /// Attempt to close the socket, see [`PollIo::poll_close`]
+    pub fn poll_close(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_close(context)
+    }
+}
+
+impl From<TcpStream> for PollIo {
+    fn from(stream: TcpStream) -> Self {
+        stream.io
+    }
+}
+
+impl AsFd for TcpStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.as_fd()
+    }
+}
+
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.as_raw_fd()
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    io::{Error, ErrorKind, Result},
+    net::SocketAddr,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd},
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use socket2::{Domain, Protocol, SockAddr, SockRef, Socket, Type};
+use uring_operation::{
+    AncillaryBuilder,
+    BufferRing,
+    Connect,
+    Multishot,
+    Operation,
+    Recv,
+    RecvFrom,
+    RecvMsgMulti,
+    RestartPolicy,
+    Send,
+    SendMsg,
+    SendTo,
+};
+use uring_reactor::Reactor;
+
+use crate::net::internet_address;
+
+/// UDP socket sending and receiving datagrams through `io_uring`
+pub struct UdpSocket {
+    reactor: Rc<Reactor>,
+    socket: OwnedFd,
+}
+
+impl UdpSocket {
+    /// Create a socket bound to the given address
+    ///
+    /// # Errors
+    ///
+    /// If creating or binding the socket fails
+    pub fn bind(reactor: Rc<Reactor>, address: SocketAddr) -> Result<Self> {
+        let socket = Socket::new(
+            Domain::for_address(address),
+            Type::DGRAM.nonblocking(),
+            Some(Protocol::UDP),
+        )?;
+
+        socket.bind(&SockAddr::from(address))?;
+
+        Ok(Self {
+            reactor,
+            socket: socket.into(),
+        })
+    }
+
+    /// Use an already bound socket, such as one with port reuse enabled
+    ///
+    /// # Errors
+    ///
+    /// If making the socket non-blocking fails
+    pub fn from_std(reactor: Rc<Reactor>, socket: std::net::UdpSocket) -> Result<Self> {
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            reactor,
+            socket: socket.into(),
+        })
+    }
+
+    /// Set the default destination and only receive datagrams from it
+    ///
+    /// # Errors
+    ///
+    /// If connecting fails
+    pub async fn connect(&self, address: SocketAddr) -> Result<()> {
+        Connect::new(self.socket.as_fd(), SockAddr::from(address))
+            .submit_oneshot(&self.reactor)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Send a datagram to the connected peer
+    ///
+    /// # Errors
+    ///
+    /// If sending fails
+    pub async fn send(&self, data: &[u8]) -> Result<usize> {
+        Ok(Send::new(self.socket.as_fd(), data)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Send a datagram to the given address
+    ///
+    /// # Errors
+    ///
+    /// If sending fails
+    pub async fn send_to(&self, data: &[u8], address: SocketAddr) -> Result<usize> {
+        Ok(
+            SendTo::new(self.socket.as_fd(), data, SockAddr::from(address))
+                .submit_oneshot(&self.reactor)
+                .await?,
+        )
+    }
+
+    /// Send the data as datagrams of the segment size, except for a shorter
+    /// last one, from a single submission using `UDP_SEGMENT` offload
+    ///
+    /// Datagrams go to the connected peer without an address. The kernel
+    /// limits the amount of segments to 64 and the data to a single IP packet
+    /// before segmentation.
+    ///
+    /// # Errors
+    ///
+    /// If the segment size is zero or sending fails
+    pub async fn send_many(
+        &self,
+        data: &[u8],
+        segment: u16,
+        address: Option<SocketAddr>,
+    ) -> Result<usize> {
+        if segment == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "segment size must be non-zero",
+            ));
+        }
+
+        let mut send = SendMsg::new(self.socket.as_fd(), vec![data.to_vec()])
+            .ancillary(AncillaryBuilder::new().udp_segment(segment));
+
+        if let Some(address) = address {
+            send = send.address(SockAddr::from(address));
+        }
+
+        Ok(send.submit_oneshot(&self.reactor).await?)
+    }
+
+    /// Receive a datagram from the connected peer into the spare capacity of
+    /// the buffer
+    ///
+    /// # Errors
+    ///
+    /// If receiving fails
+    pub async fn recv(&self, buffer: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(Recv::new(self.socket.as_fd(), buffer)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Receive a datagram into the spare capacity of the buffer along with
+    /// the address it came from
+    ///
+    /// # Errors
+    ///
+    /// If receiving fails
+    pub async fn recv_from(&self, buffer: Vec<u8>) -> Result<(Vec<u8>, SocketAddr)> {
+        let (data, address) = RecvFrom::new(self.socket.as_fd(), buffer)
+            .submit_oneshot(&self.reactor)
+            .await?;
+
+        Ok((data, internet_address(&address)?))
+    }
+
+    /// Receive datagrams continuously from a single multishot submission
+    /// selecting buffers from the ring, which is restarted when the kernel
+    /// runs out of buffers
+    ///
+    /// Each buffer has to fit the `io_uring_recvmsg_out` header and the
+    /// address storage in front of the datagram, which is otherwise truncated.
+    pub fn recv_many<'a>(&'a self, buffers: &'a BufferRing<'a>) -> RecvMany<'a> {
+        RecvMany {
+            receive: RecvMsgMulti::new(self.socket.as_fd(), buffers)
+                .submit_multishot(&self.reactor)
+                .restart_policy(RestartPolicy::on_errors(&[libc::ENOBUFS])),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        internet_address(&SockRef::from(&self.socket).local_addr()?)
+    }
+
+    /// # Errors
+    ///
+    /// If the socket isn't connected
+    pub fn peer_addr(&self) -> Result<SocketAddr> {
+        internet_address(&SockRef::from(&self.socket).peer_addr()?)
+    }
+}
+
+impl AsFd for UdpSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+impl AsRawFd for UdpSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// Stream of datagrams and their source created by
+    /// [`UdpSocket::recv_many`]
+    #[must_use]
+    pub struct RecvMany<'a> {
+        #[pin]
+        receive: Multishot<'a, RecvMsgMulti<'a>>,
+    }
+}
+
+impl Stream for RecvMany<'_> {
+    type Item = Result<(Vec<u8>, SocketAddr)>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        self.project().receive.poll_next(context).map(|item| {
+            item.map(|result| {
+                let message = result?;
+                let address = message
+                    .address
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing source address"))?;
+
+                Ok((message.data, internet_address(&address)?))
+            })
+        })
+    }
+}
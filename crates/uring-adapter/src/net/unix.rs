@@ -0,0 +1,636 @@
+use std::{
+    ffi::OsStr,
+    future::poll_fn,
+    io::{Error, ErrorKind, IoSlice, Result},
+    mem::MaybeUninit,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use socket2::{Domain, SockAddr, SockRef, Socket, Type};
+use uring_operation::{
+    Accept,
+    AncillaryBuilder,
+    AncillaryMessage,
+    Connect,
+    MessageFlags,
+    Operation,
+    Recv,
+    RecvFrom,
+    RecvMsg,
+    Send,
+    SendMsg,
+    SendTo,
+};
+use uring_reactor::Reactor;
+
+use crate::{net::BACKLOG, PollIo, ReadHalf, WriteHalf};
+
+/// Create an address in the abstract namespace, which isn't bound to the
+/// file system
+///
+/// # Errors
+///
+/// If the name is too long
+pub fn abstract_address(name: &[u8]) -> Result<SockAddr> {
+    let mut path = vec![0];
+    path.extend_from_slice(name);
+
+    SockAddr::unix(OsStr::from_bytes(&path))
+}
+
+/// Unix stream socket accepting connections through `io_uring`
+pub struct UnixListener {
+    reactor: Rc<Reactor>,
+    socket: OwnedFd,
+}
+
+impl UnixListener {
+    /// Create a listener bound to the given path
+    ///
+    /// # Errors
+    ///
+    /// If creating, binding or listening on the socket fails
+    pub fn bind(reactor: Rc<Reactor>, path: impl AsRef<Path>) -> Result<Self> {
+        Self::bind_addr(reactor, &SockAddr::unix(path)?)
+    }
+
+    /// Create a listener bound to the given address, such as one in the
+    /// abstract namespace
+    ///
+    /// # Errors
+    ///
+    /// If creating, binding or listening on the socket fails
+    pub fn bind_addr(reactor: Rc<Reactor>, address: &SockAddr) -> Result<Self> {
+        let socket = Socket::new(Domain::UNIX, Type::STREAM.nonblocking(), None)?;
+
+        socket.bind(address)?;
+        socket.listen(BACKLOG)?;
+
+        Ok(Self {
+            reactor,
+            socket: socket.into(),
+        })
+    }
+
+    /// Use an already listening socket
+    ///
+    /// # Errors
+    ///
+    /// If making the socket non-blocking fails
+    pub fn from_std(
+        reactor: Rc<Reactor>,
+        listener: std::os::unix::net::UnixListener,
+    ) -> Result<Self> {
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            reactor,
+            socket: listener.into(),
+        })
+    }
+
+    /// Accept a single connection
+    ///
+    /// # Errors
+    ///
+    /// If accepting fails
+    pub async fn accept(&self) -> Result<(UnixStream, SockAddr)> {
+        let (socket, address) = Accept::new(self.socket.as_fd())
+            .non_blocking_socket()
+            .close_socket_on_exec()
+            .submit_oneshot(&self.reactor)
+            .await?;
+
+        Ok((
+            UnixStream::from_socket(self.reactor.clone(), socket),
+            address,
+        ))
+    }
+
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn local_addr(&self) -> Result<SockAddr> {
+        SockRef::from(&self.socket).local_addr()
+    }
+}
+
+impl AsFd for UnixListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+impl AsRawFd for UnixListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+/// Unix stream connection reading and writing through `io_uring`
+///
+/// Writes are buffered, so they have to be flushed or the connection closed
+/// before dropping it to not lose any data, see [`PollIo`]
+pub struct UnixStream {
+    io: PollIo,
+}
+
+impl UnixStream {
+    const fn from_socket(reactor: Rc<Reactor>, socket: OwnedFd) -> Self {
+        Self {
+            io: PollIo::new(reactor, socket),
+        }
+    }
+
+    /// Open a connection to the socket bound to the given path
+    ///
+    /// # Errors
+    ///
+    /// If creating the socket or connecting fails
+    pub async fn connect(reactor: Rc<Reactor>, path: impl AsRef<Path>) -> Result<Self> {
+        Self::connect_addr(reactor, SockAddr::unix(path)?).await
+    }
+
+    /// Open a connection to the socket bound to the given address, such as
+    /// one in the abstract namespace
+    ///
+    /// # Errors
+    ///
+    /// If creating the socket or connecting fails
+    pub async fn connect_addr(reactor: Rc<Reactor>, address: SockAddr) -> Result<Self> {
+        let socket = Socket::new(Domain::UNIX, Type::STREAM.nonblocking(), None)?;
+
+        Connect::new(socket.as_fd(), address)
+            .submit_oneshot(&reactor)
+            .await?;
+
+        Ok(Self::from_socket(reactor, socket.into()))
+    }
+
+    /// Create a pair of connected streams
+    ///
+    /// # Errors
+    ///
+    /// If creating the sockets fails
+    pub fn pair(reactor: &Rc<Reactor>) -> Result<(Self, Self)> {
+        let (first, second) = Socket::pair(Domain::UNIX, Type::STREAM.nonblocking(), None)?;
+
+        Ok((
+            Self::from_socket(reactor.clone(), first.into()),
+            Self::from_socket(reactor.clone(), second.into()),
+        ))
+    }
+
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn local_addr(&self) -> Result<SockAddr> {
+        SockRef::from(&self.io).local_addr()
+    }
+
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn peer_addr(&self) -> Result<SockAddr> {
+        SockRef::from(&self.io).peer_addr()
+    }
+
+    /// Credentials of the process that connected or created the pair
+    ///
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn peer_cred(&self) -> Result<libc::ucred> {
+        peer_credentials(self.io.as_fd())
+    }
+
+    /// Send data along with duplicates of the files, after flushing buffered
+    /// writes
+    ///
+    /// # Errors
+    ///
+    /// If flushing or sending fails
+    pub async fn send_fds(&mut self, data: &[u8], files: &[BorrowedFd<'_>]) -> Result<usize> {
+        poll_fn(|context| Pin::new(&mut self.io).poll_flush(context)).await?;
+
+        send_with_files(&self.io.reactor, self.io.as_fd(), data, files, None).await
+    }
+
+    /// Receive data into the spare capacity of the buffer along with files,
+    /// with room for at least the given amount of them where any files beyond
+    /// that room are closed by the kernel
+    ///
+    /// This bypasses the internal read buffer, so it shouldn't be mixed with
+    /// buffered reads that may hold on to data.
+    ///
+    /// # Errors
+    ///
+    /// If asking for room for more than 253 files, which the kernel never
+    /// passes in a single message, or receiving fails
+    pub async fn recv_fds(
+        &mut self,
+        buffer: Vec<u8>,
+        files: usize,
+    ) -> Result<(Vec<u8>, Vec<OwnedFd>)> {
+        let (data, files, _) =
+            receive_with_files(&self.io.reactor, self.io.as_fd(), buffer, files).await?;
+
+        Ok((data, files))
+    }
+
+    /// Split into halves that can be used independently, see
+    /// [`PollIo::into_split`]
+    #[must_use]
+    pub fn into_split(self) -> (ReadHalf, WriteHalf) {
+        self.io.into_split()
+    }
+
+    /// Attempt to read into the buffer, see [`PollIo::poll_read`]
+    pub fn poll_read(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffer: &mut [MaybeUninit<u8>],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_read(context, buffer)
+    }
+
+    /// Attempt to write the buffer's contents, see [`PollIo::poll_write`]
+    pub fn poll_write(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffer: &[u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(context, buffer)
+    }
+
+    /// Attempt to write the contents of multiple buffers, see
+    /// [`PollIo::poll_write_vectored`]
+    pub fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffers: &[IoSlice],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(context, buffers)
+    }
+
+    /// Attempt to write all buffered contents, see [`PollIo::poll_flush`]
+    pub fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(context)
+    }
+
+    /// Attempt to shutdown the socket, see [`PollIo::poll_shutdown`]
+    pub fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(context)
+    }
+
+    /// Attempt to close the socket, see [`PollIo::poll_close`]
+    pub fn poll_close(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_close(context)
+    }
+}
+
+impl From<UnixStream> for PollIo {
+    fn from(stream: UnixStream) -> Self {
+        stream.io
+    }
+}
+
+impl AsFd for UnixStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.as_fd()
+    }
+}
+
+impl AsRawFd for UnixStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.as_raw_fd()
+    }
+}
+
+/// Unix datagram socket sending and receiving through `io_uring`
+pub struct UnixDatagram {
+    reactor: Rc<Reactor>,
+    socket: OwnedFd,
+}
+
+impl UnixDatagram {
+    /// Create a socket bound to the given path
+    ///
+    /// # Errors
+    ///
+    /// If creating or binding the socket fails
+    pub fn bind(reactor: Rc<Reactor>, path: impl AsRef<Path>) -> Result<Self> {
+        Self::bind_addr(reactor, &SockAddr::unix(path)?)
+    }
+
+    /// Create a socket bound to the given address, such as one in the
+    /// abstract namespace
+    ///
+    /// # Errors
+    ///
+    /// If creating or binding the socket fails
+    pub fn bind_addr(reactor: Rc<Reactor>, address: &SockAddr) -> Result<Self> {
+        let socket = Self::unbound(reactor)?;
+        SockRef::from(&socket.socket).bind(address)?;
+
+        Ok(socket)
+    }
+
+    /// Create a socket that isn't bound to any address
+    ///
+    /// # Errors
+    ///
+    /// If creating the socket fails
+    pub fn unbound(reactor: Rc<Reactor>) -> Result<Self> {
+        let socket = Socket::new(Domain::UNIX, Type::DGRAM.nonblocking(), None)?;
+
+        Ok(Self {
+            reactor,
+            socket: socket.into(),
+        })
+    }
+
+    /// Create a pair of connected sockets
+    ///
+    /// # Errors
+    ///
+    /// If creating the sockets fails
+    pub fn pair(reactor: &Rc<Reactor>) -> Result<(Self, Self)> {
+        let (first, second) = Socket::pair(Domain::UNIX, Type::DGRAM.nonblocking(), None)?;
+
+        Ok((
+            Self {
+                reactor: reactor.clone(),
+                socket: first.into(),
+            },
+            Self {
+                reactor: reactor.clone(),
+                socket: second.into(),
+            },
+        ))
+    }
+
+    /// Use an already created socket
+    ///
+    /// # Errors
+    ///
+    /// If making the socket non-blocking fails
+    pub fn from_std(
+        reactor: Rc<Reactor>,
+        socket: std::os::unix::net::UnixDatagram,
+    ) -> Result<Self> {
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            reactor,
+            socket: socket.into(),
+        })
+    }
+
+    /// Set the default destination to the socket bound to the given path and
+    /// only receive datagrams from it
+    ///
+    /// # Errors
+    ///
+    /// If connecting fails
+    pub async fn connect(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.connect_addr(SockAddr::unix(path)?).await
+    }
+
+    /// Set the default destination to the given address, such as one in the
+    /// abstract namespace
+    ///
+    /// # Errors
+    ///
+    /// If connecting fails
+    pub async fn connect_addr(&self, address: SockAddr) -> Result<()> {
+        Connect::new(self.socket.as_fd(), address)
+            .submit_oneshot(&self.reactor)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Send a datagram to the connected peer
+    ///
+    /// # Errors
+    ///
+    /// If sending fails
+    pub async fn send(&self, data: &[u8]) -> Result<usize> {
+        Ok(Send::new(self.socket.as_fd(), data)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Send a datagram to the socket bound to the given path
+    ///
+    /// # Errors
+    ///
+    /// If sending fails
+    pub async fn send_to(&self, data: &[u8], path: impl AsRef<Path>) -> Result<usize> {
+        self.send_to_addr(data, SockAddr::unix(path)?).await
+    }
+
+    /// Send a datagram to the given address, such as one in the abstract
+    /// namespace
+    ///
+    /// # Errors
+    ///
+    /// If sending fails
+    pub async fn send_to_addr(&self, data: &[u8], address: SockAddr) -> Result<usize> {
+        Ok(SendTo::new(self.socket.as_fd(), data, address)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Receive a datagram into the spare capacity of the buffer
+    ///
+    /// # Errors
+    ///
+    /// If receiving fails
+    pub async fn recv(&self, buffer: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(Recv::new(self.socket.as_fd(), buffer)
+            .submit_oneshot(&self.reactor)
+            .await?)
+    }
+
+    /// Receive a datagram into the spare capacity of the buffer along with
+    /// the address it came from, which is unnamed for unbound senders
+    ///
+    /// # Errors
+    ///
+    /// If receiving fails
+    pub async fn recv_from(&self, buffer: Vec<u8>) -> Result<(Vec<u8>, SockAddr)> {
+        let (data, address) = RecvFrom::new(self.socket.as_fd(), buffer)
+            .submit_oneshot(&self.reactor)
+            .await?;
+
+        // the kernel reports no address at all for unbound senders
+        if address.len() == 0 {
+            return Ok((data, SockAddr::unix("")?));
+        }
+
+        Ok((data, address))
+    }
+
+    /// Send a datagram along with duplicates of the files, to the given
+    /// address or the connected peer
+    ///
+    /// # Errors
+    ///
+    /// If sending fails
+    pub async fn send_fds(
+        &self,
+        data: &[u8],
+        files: &[BorrowedFd<'_>],
+        address: Option<SockAddr>,
+    ) -> Result<usize> {
+        send_with_files(&self.reactor, self.socket.as_fd(), data, files, address).await
+    }
+
+    /// Receive a datagram into the spare capacity of the buffer along with
+    /// files and the address it came from, with room for at least the given
+    /// amount of files where any beyond that room are closed by the kernel
+    ///
+    /// # Errors
+    ///
+    /// If asking for room for more than 253 files, which the kernel never
+    /// passes in a single message, or receiving fails
+    pub async fn recv_fds(
+        &self,
+        buffer: Vec<u8>,
+        files: usize,
+    ) -> Result<(Vec<u8>, Vec<OwnedFd>, Option<SockAddr>)> {
+        receive_with_files(&self.reactor, self.socket.as_fd(), buffer, files).await
+    }
+
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn local_addr(&self) -> Result<SockAddr> {
+        SockRef::from(&self.socket).local_addr()
+    }
+
+    /// # Errors
+    ///
+    /// If the socket isn't connected
+    pub fn peer_addr(&self) -> Result<SockAddr> {
+        SockRef::from(&self.socket).peer_addr()
+    }
+
+    /// Credentials of the process that connected or created the pair
+    ///
+    /// # Errors
+    ///
+    /// If querying the socket fails
+    pub fn peer_cred(&self) -> Result<libc::ucred> {
+        peer_credentials(self.socket.as_fd())
+    }
+}
+
+impl AsFd for UnixDatagram {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+impl AsRawFd for UnixDatagram {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+/// Query `SO_PEERCRED` of a connected socket
+fn peer_credentials(socket: BorrowedFd) -> Result<libc::ucred> {
+    let mut credentials = MaybeUninit::<libc::ucred>::zeroed();
+    let mut length = libc::socklen_t::try_from(std::mem::size_of::<libc::ucred>()).unwrap();
+
+    // SAFETY: the storage and length match and the socket is borrowed
+    let result = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            credentials.as_mut_ptr().cast(),
+            &raw mut length,
+        )
+    };
+
+    if result == -1 {
+        return Err(Error::last_os_error());
+    }
+
+    // SAFETY: the kernel filled in the credentials
+    Ok(unsafe { credentials.assume_init() })
+}
+
+/// Most files the kernel passes in a single `SCM_RIGHTS` message
+const SCM_MAX_FD: usize = 253;
+
+/// Send data with an `SCM_RIGHTS` control message
+async fn send_with_files(
+    reactor: &Reactor,
+    socket: BorrowedFd<'_>,
+    data: &[u8],
+    files: &[BorrowedFd<'_>],
+    address: Option<SockAddr>,
+) -> Result<usize> {
+    let mut send =
+        SendMsg::new(socket, vec![data.to_vec()]).ancillary(AncillaryBuilder::new().rights(files));
+
+    if let Some(address) = address {
+        send = send.address(address);
+    }
+
+    Ok(send.submit_oneshot(reactor).await?)
+}
+
+/// Receive data with space for an `SCM_RIGHTS` control message of the given
+/// amount of files, which are closed on exec
+async fn receive_with_files(
+    reactor: &Reactor,
+    socket: BorrowedFd<'_>,
+    buffer: Vec<u8>,
+    files: usize,
+) -> Result<(Vec<u8>, Vec<OwnedFd>, Option<SockAddr>)> {
+    // the kernel never passes more files in a single message, which also
+    // keeps the size of the control message from overflowing
+    let length = Some(files)
+        .filter(|files| *files <= SCM_MAX_FD)
+        .and_then(|files| files.checked_mul(std::mem::size_of::<RawFd>()))
+        .and_then(|length| u32::try_from(length).ok())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "can't receive more than 253 files in a single message",
+            )
+        })?;
+
+    // SAFETY: only does arithmetic
+    let capacity = unsafe { libc::CMSG_SPACE(length) } as usize;
+
+    let message = RecvMsg::new(socket, buffer)
+        .ancillary_capacity(capacity)
+        .flags(MessageFlags::CMSG_CLOSE_ON_EXEC)
+        .submit_oneshot(reactor)
+        .await?;
+
+    let files = message
+        .ancillary
+        .into_iter()
+        .filter_map(|message| match message {
+            AncillaryMessage::Rights(files) => Some(files),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+
+    Ok((message.data, files, message.address))
+}
@@ -0,0 +1,119 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{IoSlice, Result},
+    mem::MaybeUninit,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use danger_cell::DangerCell;
+
+use crate::PollIo;
+
+impl PollIo {
+    /// Split into halves that can be used independently, such as from
+    /// separate tasks, where the file is closed once both are dropped
+    #[must_use]
+    pub fn into_split(self) -> (ReadHalf, WriteHalf) {
+        let io = Rc::new(DangerCell::new(self));
+
+        (ReadHalf { io: io.clone() }, WriteHalf { io })
+    }
+}
+
+/// Reading half of a [`PollIo`] created by [`PollIo::into_split`]
+pub struct ReadHalf {
+    io: Rc<DangerCell<PollIo>>,
+}
+
+impl ReadHalf {
+    /// Rejoin with the writing half from the same split
+    ///
+    /// # Errors
+    ///
+    /// If the halves originate from different splits, handing both back
+    pub fn reunite(self, other: WriteHalf) -> std::result::Result<PollIo, ReuniteError> {
+        if !Rc::ptr_eq(&self.io, &other.io) {
+            return Err(ReuniteError(self, other));
+        }
+
+        drop(other);
+
+        let Ok(io) = Rc::try_unwrap(self.io) else {
+            unreachable!("both halves were given back");
+        };
+
+        Ok(io.into_inner())
+    }
+
+    /// Attempt to read into the buffer, in the same way as
+    /// [`PollIo::poll_read`]
+    pub fn poll_read(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffer: &mut [MaybeUninit<u8>],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut *self.io.assume_unique_access()).poll_read(context, buffer)
+    }
+}
+
+/// Writing half of a [`PollIo`] created by [`PollIo::into_split`]
+pub struct WriteHalf {
+    io: Rc<DangerCell<PollIo>>,
+}
+
+impl WriteHalf {
+    /// Attempt to write the buffer's contents, in the same way as
+    /// [`PollIo::poll_write`]
+    pub fn poll_write(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffer: &[u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut *self.io.assume_unique_access()).poll_write(context, buffer)
+    }
+
+    /// Attempt to write the contents of multiple buffers, in the same way as
+    /// [`PollIo::poll_write`]
+    pub fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buffers: &[IoSlice],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut *self.io.assume_unique_access()).poll_write_vectored(context, buffers)
+    }
+
+    /// Attempt to write all buffered contents
+    pub fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        Pin::new(&mut *self.io.assume_unique_access()).poll_flush(context)
+    }
+
+    /// Attempt to shutdown the writing direction of the socket after flushing
+    /// buffered writes, which leaves reads of the other half running
+    pub fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<()>> {
+        self.io
+            .assume_unique_access()
+            .poll_shutdown_with(context, false)
+    }
+}
+
+/// Error when trying to reunite halves from different splits, holding both
+/// of them
+pub struct ReuniteError(pub ReadHalf, pub WriteHalf);
+
+impl fmt::Debug for ReuniteError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_tuple("ReuniteError")
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for ReuniteError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("tried to reunite halves from different splits")
+    }
+}
+
+impl std::error::Error for ReuniteError {}
@@ -0,0 +1,132 @@
+use std::{future::Future, io::ErrorKind, os::unix::fs::OpenOptionsExt, path::PathBuf, rc::Rc};
+
+use io_uring::IoUring;
+use uring_adapter::fs::{AlignedBuffer, File};
+use uring_reactor::Reactor;
+
+/// Path for a temporary file that's unique to the test
+fn temporary_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("uring-adapter-fs-{name}-{}", std::process::id()))
+}
+
+fn run<F: Future>(reactor: &Reactor, future: F) -> F::Output {
+    local_fifo_executor::block_on(future, || reactor.tick()).unwrap()
+}
+
+#[test]
+fn written_contents_read_back() {
+    let reactor = Rc::new(Reactor::new(IoUring::new(8).unwrap()));
+    let path = temporary_path("contents");
+
+    run(&reactor, async {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(reactor.clone(), &path)
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let written = file.write_all_at(b"hello world".to_vec(), 0).await.unwrap();
+        assert_eq!(written, b"hello world");
+
+        let read = file.read_exact_at(Vec::with_capacity(5), 6).await.unwrap();
+        assert_eq!(read, b"world");
+
+        // reading appends to the existing contents
+        let mut buffer = Vec::with_capacity(17);
+        buffer.extend_from_slice(b"hello ");
+        let read = file.read_at(buffer, 0).await.unwrap();
+        assert_eq!(read, b"hello hello world");
+
+        let error = file
+            .read_exact_at(Vec::with_capacity(8), 6)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+
+        file.close().await.unwrap();
+    });
+}
+
+#[test]
+fn set_len_changes_metadata() {
+    let reactor = Rc::new(Reactor::new(IoUring::new(8).unwrap()));
+    let path = temporary_path("length");
+
+    run(&reactor, async {
+        let file = File::create(reactor.clone(), &path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let metadata = file.metadata().await.unwrap();
+        assert!(metadata.is_file());
+        assert!(metadata.is_empty());
+
+        file.set_len(4096).await.unwrap();
+        file.sync_all().await.unwrap();
+
+        let metadata = file.metadata().await.unwrap();
+        assert_eq!(metadata.len(), 4096);
+        assert!(metadata.modified().is_ok());
+
+        file.set_len(16).await.unwrap();
+        file.sync_data().await.unwrap();
+        assert_eq!(file.metadata().await.unwrap().len(), 16);
+    });
+}
+
+#[test]
+fn from_std_detects_direct_io() {
+    let reactor = Rc::new(Reactor::new(IoUring::new(8).unwrap()));
+    let path = temporary_path("direct");
+
+    let buffered = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+
+    let direct = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(&path)
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    run(&reactor, async {
+        let buffered = File::from_std(reactor.clone(), buffered).await.unwrap();
+        assert_eq!(buffered.alignment(), None);
+
+        let direct = File::from_std(reactor.clone(), direct).await.unwrap();
+        let alignment = direct.alignment().unwrap();
+
+        // unaligned transfers are rejected before reaching the kernel
+        let error = direct.write_at(b"unaligned".to_vec(), 0).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        let mut buffer = AlignedBuffer::new(alignment, alignment);
+        buffer.extend_from_slice(&vec![7; alignment]);
+        direct.write_all_at(buffer, 0).await.unwrap();
+
+        let read = direct
+            .read_exact_at(AlignedBuffer::new(alignment, alignment), 0)
+            .await
+            .unwrap();
+        assert!(read.iter().all(|&byte| byte == 7));
+
+        // the file ends in the middle of the first block
+        direct.set_len(1).await.unwrap();
+        let Err(error) = direct
+            .read_exact_at(AlignedBuffer::new(alignment, alignment), 0)
+            .await
+        else {
+            panic!("expected the short read to end the file");
+        };
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    });
+}
@@ -0,0 +1,101 @@
+use std::{
+    future::poll_fn,
+    io::Write,
+    mem::MaybeUninit,
+    os::unix::net::UnixStream,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use io_uring::IoUring;
+use uring_adapter::PollIo;
+use uring_reactor::Reactor;
+
+#[test]
+fn closed_file_is_not_used() {
+    let reactor = Rc::new(Reactor::new(IoUring::new(8).unwrap()));
+    let (file, _peer) = UnixStream::pair().unwrap();
+    let mut io = PollIo::new(reactor.clone(), file.into());
+
+    local_fifo_executor::block_on(
+        poll_fn(|context| Pin::new(&mut io).poll_close(context)),
+        || reactor.tick(),
+    )
+    .unwrap()
+    .unwrap();
+
+    let mut buffer = [MaybeUninit::uninit(); 8];
+    let read = local_fifo_executor::block_on(
+        poll_fn(|context| Pin::new(&mut io).poll_read(context, &mut buffer)),
+        || reactor.tick(),
+    )
+    .unwrap();
+    assert_eq!(read.unwrap_err().raw_os_error(), Some(libc::EBADF));
+
+    let written = local_fifo_executor::block_on(
+        poll_fn(|context| Pin::new(&mut io).poll_write(context, b"closed")),
+        || reactor.tick(),
+    )
+    .unwrap();
+    assert_eq!(written.unwrap_err().raw_os_error(), Some(libc::EBADF));
+}
+
+/// Read once into a fresh buffer and return what was read
+fn read(reactor: &Reactor, io: &mut PollIo) -> Vec<u8> {
+    let mut buffer = [MaybeUninit::uninit(); 16];
+    let amount = local_fifo_executor::block_on(
+        poll_fn(|context| Pin::new(&mut *io).poll_read(context, &mut buffer)),
+        || reactor.tick(),
+    )
+    .unwrap()
+    .unwrap();
+
+    // SAFETY: the amount was initialized by the read
+    buffer[..amount]
+        .iter()
+        .map(|byte| unsafe { byte.assume_init() })
+        .collect()
+}
+
+#[test]
+fn empty_read_does_not_start_reading() {
+    let reactor = Rc::new(Reactor::new(IoUring::new(8).unwrap()));
+    let (file, mut peer) = UnixStream::pair().unwrap();
+    let mut io = PollIo::new(reactor.clone(), file.into()).with_read_buffer(16);
+
+    let mut context = Context::from_waker(Waker::noop());
+    assert!(matches!(
+        Pin::new(&mut io).poll_read(&mut context, &mut []),
+        Poll::Ready(Ok(0))
+    ));
+
+    peer.write_all(b"data").unwrap();
+    assert_eq!(read(&reactor, &mut io), b"data");
+}
+
+#[test]
+fn shutdown_keeps_read_completed_before_cancelling() {
+    let reactor = Rc::new(Reactor::new(IoUring::new(8).unwrap()));
+    let (file, mut peer) = UnixStream::pair().unwrap();
+    let mut io = PollIo::new(reactor.clone(), file.into()).with_read_buffer(16);
+
+    let mut buffer = [MaybeUninit::uninit(); 16];
+    let mut context = Context::from_waker(Waker::noop());
+    assert!(Pin::new(&mut io)
+        .poll_read(&mut context, &mut buffer)
+        .is_pending());
+
+    // the read completes before the cancellation gets submitted
+    peer.write_all(b"late").unwrap();
+    reactor.tick().unwrap();
+
+    local_fifo_executor::block_on(
+        poll_fn(|context| Pin::new(&mut io).poll_shutdown(context)),
+        || reactor.tick(),
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(read(&reactor, &mut io), b"late");
+}
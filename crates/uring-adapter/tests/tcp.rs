@@ -0,0 +1,155 @@
+use std::{
+    future::{poll_fn, Future},
+    io::Read,
+    mem::MaybeUninit,
+    net::SocketAddr,
+    pin::{pin, Pin},
+    rc::Rc,
+    task::{Context, Waker},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use io_uring::IoUring;
+use uring_adapter::net::{TcpListener, TcpStream};
+use uring_operation::{Nop, Operation};
+use uring_reactor::Reactor;
+
+fn reactor() -> Rc<Reactor> {
+    Rc::new(Reactor::new(IoUring::new(8).unwrap()))
+}
+
+fn listen(reactor: &Rc<Reactor>) -> (TcpListener, SocketAddr) {
+    let listener = TcpListener::bind(reactor.clone(), "127.0.0.1:0".parse().unwrap()).unwrap();
+    let address = listener.local_addr().unwrap();
+    (listener, address)
+}
+
+fn run<F: Future>(reactor: &Reactor, future: F) -> F::Output {
+    local_fifo_executor::block_on(future, || reactor.tick()).unwrap()
+}
+
+/// Poll the future once so that its operation gets submitted, then drop it
+fn poll_once(future: impl Future) {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    assert!(future.as_mut().poll(&mut context).is_pending());
+}
+
+/// Wait for the cancellations submitted so far to take effect
+fn settle(reactor: &Reactor) {
+    run(reactor, Nop::new().submit_oneshot(reactor)).unwrap();
+}
+
+async fn write_all(stream: &mut TcpStream, mut data: &[u8]) {
+    while !data.is_empty() {
+        let amount = poll_fn(|context| Pin::new(&mut *stream).poll_write(context, data))
+            .await
+            .unwrap();
+        data = &data[amount..];
+    }
+
+    poll_fn(|context| Pin::new(&mut *stream).poll_flush(context))
+        .await
+        .unwrap();
+}
+
+async fn read(stream: &mut TcpStream) -> Vec<u8> {
+    let mut buffer = [MaybeUninit::uninit(); 64];
+    let amount = poll_fn(|context| Pin::new(&mut *stream).poll_read(context, &mut buffer))
+        .await
+        .unwrap();
+
+    // SAFETY: the amount was initialized by the read
+    buffer[..amount]
+        .iter()
+        .map(|byte| unsafe { byte.assume_init() })
+        .collect()
+}
+
+#[test]
+fn connected_streams_exchange_data() {
+    let reactor = reactor();
+    let (listener, address) = listen(&reactor);
+
+    run(&reactor, async {
+        // the kernel completes the handshake before the connection is accepted
+        let mut client = TcpStream::connect(reactor.clone(), address).await.unwrap();
+        let (mut server, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer, client.local_addr().unwrap());
+        assert_eq!(server.peer_addr().unwrap(), client.local_addr().unwrap());
+
+        write_all(&mut client, b"ping").await;
+        assert_eq!(read(&mut server).await, b"ping");
+
+        write_all(&mut server, b"pong").await;
+        assert_eq!(read(&mut client).await, b"pong");
+
+        poll_fn(|context| Pin::new(&mut client).poll_close(context))
+            .await
+            .unwrap();
+        assert_eq!(read(&mut server).await, b"");
+    });
+}
+
+#[test]
+fn incoming_yields_connections() {
+    let reactor = reactor();
+    let (listener, address) = listen(&reactor);
+    let mut incoming = pin!(listener.incoming());
+
+    for _ in 0..2 {
+        let client = std::net::TcpStream::connect(address).unwrap();
+        let (_, peer) = run(
+            &reactor,
+            poll_fn(|context| incoming.as_mut().poll_next(context)),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(peer, Some(client.local_addr().unwrap()));
+    }
+}
+
+#[test]
+fn dropped_incoming_closes_queued_connections() {
+    let reactor = reactor();
+    let (listener, address) = listen(&reactor);
+
+    let mut queued = {
+        let mut incoming = pin!(listener.incoming());
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(incoming.as_mut().poll_next(&mut context).is_pending());
+
+        // the accepted connection is queued without anyone taking it
+        let queued = std::net::TcpStream::connect(address).unwrap();
+        reactor.tick().unwrap();
+        queued
+    };
+
+    settle(&reactor);
+
+    queued
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut buffer = [0; 4];
+    assert_eq!(queued.read(&mut buffer).unwrap(), 0);
+
+    // later connections are left for other accepts
+    let client = std::net::TcpStream::connect(address).unwrap();
+    let (_, peer) = run(&reactor, listener.accept()).unwrap();
+    assert_eq!(peer, client.local_addr().unwrap());
+}
+
+#[test]
+fn dropped_accept_leaves_connections() {
+    let reactor = reactor();
+    let (listener, address) = listen(&reactor);
+
+    poll_once(listener.accept());
+    settle(&reactor);
+
+    let client = std::net::TcpStream::connect(address).unwrap();
+    let (_, peer) = run(&reactor, listener.accept()).unwrap();
+    assert_eq!(peer, client.local_addr().unwrap());
+}
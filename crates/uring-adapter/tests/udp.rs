@@ -0,0 +1,147 @@
+use std::{
+    future::{poll_fn, Future},
+    io::ErrorKind,
+    pin::pin,
+    rc::Rc,
+    task::{Context, Waker},
+};
+
+use futures_core::Stream;
+use io_uring::IoUring;
+use uring_adapter::net::UdpSocket;
+use uring_operation::{BufferRing, Nop, Operation};
+use uring_reactor::Reactor;
+
+fn reactor() -> Rc<Reactor> {
+    Rc::new(Reactor::new(IoUring::new(8).unwrap()))
+}
+
+fn bind(reactor: &Rc<Reactor>) -> UdpSocket {
+    UdpSocket::bind(reactor.clone(), "127.0.0.1:0".parse().unwrap()).unwrap()
+}
+
+/// Wait for the cancellations submitted so far to take effect
+fn settle(reactor: &Reactor) {
+    local_fifo_executor::block_on(Nop::new().submit_oneshot(reactor), || reactor.tick())
+        .unwrap()
+        .unwrap();
+}
+
+/// Send a datagram and receive it again, which a receive that wasn't
+/// cancelled would take instead
+fn assert_received(reactor: &Reactor, sender: &UdpSocket, receiver: &UdpSocket) {
+    let target = receiver.local_addr().unwrap();
+    local_fifo_executor::block_on(sender.send_to(b"kept", target), || reactor.tick())
+        .unwrap()
+        .unwrap();
+
+    let (data, _) =
+        local_fifo_executor::block_on(receiver.recv_from(Vec::with_capacity(16)), || {
+            reactor.tick()
+        })
+        .unwrap()
+        .unwrap();
+    assert_eq!(data, b"kept");
+}
+
+#[test]
+fn send_many_segments() {
+    let reactor = reactor();
+    let (sender, receiver) = (bind(&reactor), bind(&reactor));
+    let target = Some(receiver.local_addr().unwrap());
+
+    let sent = local_fifo_executor::block_on(sender.send_many(b"0123456789", 4, target), || {
+        reactor.tick()
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(sent, 10);
+
+    for expected in [&b"0123"[..], b"4567", b"89"] {
+        let (data, source) =
+            local_fifo_executor::block_on(receiver.recv_from(Vec::with_capacity(16)), || {
+                reactor.tick()
+            })
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(data, expected);
+        assert_eq!(source, sender.local_addr().unwrap());
+    }
+}
+
+#[test]
+fn send_many_rejects_empty_segments() {
+    let reactor = reactor();
+    let (sender, receiver) = (bind(&reactor), bind(&reactor));
+    let target = Some(receiver.local_addr().unwrap());
+
+    let error =
+        local_fifo_executor::block_on(sender.send_many(b"data", 0, target), || reactor.tick())
+            .unwrap()
+            .unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+/// More datagrams than buffers in the ring make the kernel end the multishot
+/// receive with `ENOBUFS`, which the stream recovers from
+#[test]
+fn recv_many_restarts_on_exhausted_buffers() {
+    let reactor = reactor();
+    let (sender, receiver) = (bind(&reactor), bind(&reactor));
+    let target = receiver.local_addr().unwrap();
+
+    for datagram in [b"one", b"two", b"six", b"ten"] {
+        local_fifo_executor::block_on(sender.send_to(datagram, target), || reactor.tick())
+            .unwrap()
+            .unwrap();
+    }
+
+    let buffers = BufferRing::new(&reactor, 0, 2, 256).unwrap();
+    let mut stream = pin!(receiver.recv_many(&buffers));
+
+    for expected in [b"one", b"two", b"six", b"ten"] {
+        let (data, source) = local_fifo_executor::block_on(
+            poll_fn(|context| stream.as_mut().poll_next(context)),
+            || reactor.tick(),
+        )
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(data, expected);
+        assert_eq!(source, sender.local_addr().unwrap());
+    }
+}
+
+#[test]
+fn dropped_recv_from_is_cancelled() {
+    let reactor = reactor();
+    let (sender, receiver) = (bind(&reactor), bind(&reactor));
+
+    {
+        let mut receive = pin!(receiver.recv_from(Vec::with_capacity(16)));
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(receive.as_mut().poll(&mut context).is_pending());
+    }
+
+    settle(&reactor);
+    assert_received(&reactor, &sender, &receiver);
+}
+
+#[test]
+fn dropped_recv_many_is_cancelled() {
+    let reactor = reactor();
+    let (sender, receiver) = (bind(&reactor), bind(&reactor));
+    let buffers = BufferRing::new(&reactor, 0, 2, 256).unwrap();
+
+    {
+        let mut stream = pin!(receiver.recv_many(&buffers));
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(stream.as_mut().poll_next(&mut context).is_pending());
+    }
+
+    settle(&reactor);
+    assert_received(&reactor, &sender, &receiver);
+}
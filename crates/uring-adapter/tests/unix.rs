@@ -0,0 +1,115 @@
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Seek, Write},
+    os::fd::AsFd,
+    rc::Rc,
+};
+
+use io_uring::IoUring;
+use uring_adapter::net::{UnixDatagram, UnixStream};
+use uring_reactor::Reactor;
+
+fn reactor() -> Rc<Reactor> {
+    Rc::new(Reactor::new(IoUring::new(8).unwrap()))
+}
+
+/// Unlinked file with known contents to tell it apart after passing it
+fn marked_file(name: &str) -> File {
+    let path = std::env::temp_dir().join(format!("uring-adapter-{name}-{}", std::process::id()));
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+
+    std::fs::remove_file(path).unwrap();
+    file.write_all(b"passed").unwrap();
+    file
+}
+
+fn contents(mut file: File) -> String {
+    let mut contents = String::new();
+    file.rewind().unwrap();
+    file.read_to_string(&mut contents).unwrap();
+    contents
+}
+
+#[test]
+fn stream_passes_files() {
+    let reactor = reactor();
+    let (mut sender, mut receiver) = UnixStream::pair(&reactor).unwrap();
+    let file = marked_file("stream");
+
+    let sent =
+        local_fifo_executor::block_on(sender.send_fds(b"file", &[file.as_fd()]), || reactor.tick())
+            .unwrap()
+            .unwrap();
+    assert_eq!(sent, 4);
+
+    let (data, mut files) =
+        local_fifo_executor::block_on(receiver.recv_fds(Vec::with_capacity(16), 1), || {
+            reactor.tick()
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(data, b"file");
+    assert_eq!(files.len(), 1);
+    assert_eq!(contents(File::from(files.remove(0))), "passed");
+}
+
+#[test]
+fn datagram_passes_files() {
+    let reactor = reactor();
+    let (sender, receiver) = UnixDatagram::pair(&reactor).unwrap();
+    let file = marked_file("datagram");
+
+    local_fifo_executor::block_on(sender.send_fds(b"file", &[file.as_fd()], None), || {
+        reactor.tick()
+    })
+    .unwrap()
+    .unwrap();
+
+    let (data, mut files, _) =
+        local_fifo_executor::block_on(receiver.recv_fds(Vec::with_capacity(16), 2), || {
+            reactor.tick()
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(data, b"file");
+    assert_eq!(files.len(), 1);
+    assert_eq!(contents(File::from(files.remove(0))), "passed");
+}
+
+#[test]
+fn receiving_too_many_files_is_rejected() {
+    let reactor = reactor();
+    let (_sender, receiver) = UnixDatagram::pair(&reactor).unwrap();
+
+    let error = local_fifo_executor::block_on(
+        receiver.recv_fds(Vec::with_capacity(16), usize::MAX),
+        || reactor.tick(),
+    )
+    .unwrap()
+    .unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn peer_credentials_of_pair() {
+    let reactor = reactor();
+    let (stream, _) = UnixStream::pair(&reactor).unwrap();
+    let (datagram, _) = UnixDatagram::pair(&reactor).unwrap();
+
+    for credentials in [stream.peer_cred().unwrap(), datagram.peer_cred().unwrap()] {
+        // SAFETY: querying the process identity has no preconditions
+        let (pid, uid) = unsafe { (libc::getpid(), libc::getuid()) };
+
+        assert_eq!(credentials.pid, pid);
+        assert_eq!(credentials.uid, uid);
+    }
+}
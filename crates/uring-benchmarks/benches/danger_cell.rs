@@ -0,0 +1,24 @@
+use std::{cell::RefCell, hint::black_box};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use danger_cell::DangerCell;
+
+/// Overhead of the borrow flag compared to `RefCell`
+fn borrow(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("borrow");
+
+    let cell = DangerCell::new(0_u64);
+    group.bench_function("danger_cell", |bencher| {
+        bencher.iter(|| *black_box(&cell).assume_unique_access() += 1);
+    });
+
+    let cell = RefCell::new(0_u64);
+    group.bench_function("ref_cell", |bencher| {
+        bencher.iter(|| *black_box(&cell).borrow_mut() += 1);
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, borrow);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use local_fifo_executor::Executor;
+
+const TASKS: u64 = 1024;
+
+/// Throughput of spawning tasks, which run until completion right away
+fn spawn(criterion: &mut Criterion) {
+    let executor = Executor::new();
+    let mut group = criterion.benchmark_group("executor");
+    group.throughput(Throughput::Elements(TASKS));
+
+    group.bench_function("spawn", |bencher| {
+        bencher.iter(|| {
+            for index in 0..TASKS {
+                executor.spawn(async move { black_box(index) }).detach();
+            }
+
+            executor.tick();
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, spawn);
+criterion_main!(benches);
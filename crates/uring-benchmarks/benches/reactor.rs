@@ -0,0 +1,129 @@
+use std::{
+    future::poll_fn,
+    os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd},
+    task::Context,
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use futures_core::Stream;
+use io_uring::{opcode, IoUring};
+use uring_operation::{Nop, Operation, Poll, Readiness};
+use uring_reactor::Reactor;
+
+/// Raw reactor cost of a single submission and completion without futures
+fn round_trip(criterion: &mut Criterion) {
+    let reactor = Reactor::new(IoUring::new(256).unwrap());
+    let waker = noop_waker::noop_waker();
+    let mut context = Context::from_waker(&waker);
+
+    criterion.bench_function("reactor/round_trip", |bencher| {
+        bencher.iter(|| {
+            // SAFETY: no-ops don't reference any memory
+            let handle =
+                unsafe { reactor.submit_operation(opcode::Nop::new().build(), &mut context) }
+                    .unwrap();
+
+            reactor.tick().unwrap();
+            assert!(reactor.drive_operation(handle, &mut context).is_ready());
+        });
+    });
+}
+
+/// Cost per completion of resubmitting oneshot operations compared to
+/// receiving completions from a single multishot operation
+fn oneshot_multishot(criterion: &mut Criterion) {
+    let reactor = Reactor::new(IoUring::new(256).unwrap());
+    let events = event_file();
+    let mut group = criterion.benchmark_group("operation");
+
+    group.bench_function("oneshot_nop", |bencher| {
+        bencher.iter(|| {
+            local_fifo_executor::block_on(Nop::new().submit_oneshot(&reactor), || reactor.tick())
+                .unwrap()
+                .unwrap()
+        });
+    });
+
+    group.bench_function("oneshot_poll", |bencher| {
+        bencher.iter(|| {
+            signal(&events);
+            let readiness = local_fifo_executor::block_on(
+                Poll::new(events.as_fd(), Readiness::READABLE).submit_oneshot(&reactor),
+                || reactor.tick(),
+            )
+            .unwrap()
+            .unwrap();
+
+            // otherwise the file stays readable and later polls complete
+            // right away without waiting for the signal
+            drain(&events);
+            readiness
+        });
+    });
+
+    group.bench_function("multishot_poll", |bencher| {
+        let mut stream = std::pin::pin!(Poll::new(events.as_fd(), Readiness::READABLE)
+            .multishot()
+            .submit_multishot(&reactor));
+
+        bencher.iter(|| {
+            signal(&events);
+            let readiness = local_fifo_executor::block_on(
+                poll_fn(|context| stream.as_mut().poll_next(context)),
+                || reactor.tick(),
+            )
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+            drain(&events);
+            readiness
+        });
+    });
+
+    group.finish();
+}
+
+/// Event file that wakes up pollers on every write
+fn event_file() -> OwnedFd {
+    // SAFETY: creating an event file has no preconditions
+    let file = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+    assert!(file >= 0, "failed to create event file");
+
+    // SAFETY: the descriptor was just created
+    unsafe { OwnedFd::from_raw_fd(file) }
+}
+
+fn signal(events: &OwnedFd) {
+    let value = 1_u64;
+
+    // SAFETY: the value is valid for its size
+    let written = unsafe {
+        libc::write(
+            events.as_raw_fd(),
+            (&raw const value).cast(),
+            size_of::<u64>(),
+        )
+    };
+
+    assert_eq!(written, 8, "failed to signal event file");
+}
+
+/// Reset the counter of the event file so that it stops being readable
+fn drain(events: &OwnedFd) {
+    let mut value = 0_u64;
+
+    // SAFETY: the value is valid for its size
+    let read = unsafe {
+        libc::read(
+            events.as_raw_fd(),
+            (&raw mut value).cast(),
+            size_of::<u64>(),
+        )
+    };
+
+    assert_eq!(read, 8, "failed to drain event file");
+}
+
+criterion_group!(benches, round_trip, oneshot_multishot);
+criterion_main!(benches);
@@ -0,0 +1,194 @@
+use std::{
+    marker::PhantomData,
+    os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+};
+
+/// Encoder for control messages sent alongside data
+#[derive(Default)]
+#[must_use]
+pub struct AncillaryBuilder<'a> {
+    buffer: Vec<u8>,
+    files: PhantomData<BorrowedFd<'a>>,
+}
+
+impl<'a> AncillaryBuilder<'a> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            files: PhantomData,
+        }
+    }
+
+    /// Pass duplicates of the files to the receiving process over an
+    /// `AF_UNIX` socket
+    pub fn rights(self, files: &[BorrowedFd<'a>]) -> Self {
+        let raw = files.iter().map(AsRawFd::as_raw_fd).collect::<Vec<_>>();
+        self.push(libc::SOL_SOCKET, libc::SCM_RIGHTS, &raw)
+    }
+
+    /// Assert the process credentials over an `AF_UNIX` socket
+    pub fn credentials(self, credentials: libc::ucred) -> Self {
+        self.push(libc::SOL_SOCKET, libc::SCM_CREDENTIALS, &[credentials])
+    }
+
+    /// Choose the source address and interface of an IPv4 datagram
+    pub fn packet_info(self, info: libc::in_pktinfo) -> Self {
+        self.push(libc::IPPROTO_IP, libc::IP_PKTINFO, &[info])
+    }
+
+    /// Choose the source address and interface of an IPv6 datagram
+    pub fn packet_info_v6(self, info: libc::in6_pktinfo) -> Self {
+        self.push(libc::IPPROTO_IPV6, libc::IPV6_PKTINFO, &[info])
+    }
+
+    /// Have the kernel split the data into UDP datagrams of the given size
+    pub fn udp_segment(self, size: u16) -> Self {
+        self.push(libc::SOL_UDP, libc::UDP_SEGMENT, &[size])
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    fn push<T: Copy>(mut self, level: libc::c_int, kind: libc::c_int, values: &[T]) -> Self {
+        let length = std::mem::size_of_val(values);
+        let offset = self.buffer.len();
+        self.buffer.resize(offset + space(length), 0);
+
+        // SAFETY: all zeroes is a valid header
+        let mut header: libc::cmsghdr = unsafe { std::mem::zeroed() };
+        header.cmsg_len = header_length(length);
+        header.cmsg_level = level;
+        header.cmsg_type = kind;
+
+        // SAFETY: the buffer was resized to fit both the header and data
+        unsafe {
+            let start = self.buffer.as_mut_ptr().add(offset);
+            start.cast::<libc::cmsghdr>().write_unaligned(header);
+            std::ptr::copy_nonoverlapping(
+                values.as_ptr().cast::<u8>(),
+                start.add(header_length(0)),
+                length,
+            );
+        }
+
+        self
+    }
+}
+
+/// Control message received alongside data
+#[non_exhaustive]
+pub enum AncillaryMessage {
+    Rights(Vec<OwnedFd>),
+    Credentials(libc::ucred),
+    PacketInfo(libc::in_pktinfo),
+    PacketInfoV6(libc::in6_pktinfo),
+    UdpGro(u16),
+    Timestamp(libc::timeval),
+    TimestampNs(libc::timespec),
+    Other {
+        level: libc::c_int,
+        kind: libc::c_int,
+        data: Vec<u8>,
+    },
+}
+
+impl AncillaryMessage {
+    /// Decode all control messages in the buffer
+    ///
+    /// # Safety
+    ///
+    /// The buffer must contain control data received from the kernel, which
+    /// includes passed file descriptors that become owned by the result
+    pub(crate) unsafe fn parse(buffer: &[u8]) -> Vec<Self> {
+        let mut messages = Vec::new();
+        let mut offset = 0;
+
+        while offset + header_length(0) <= buffer.len() {
+            // SAFETY: the bounds were checked above
+            let header = unsafe {
+                buffer
+                    .as_ptr()
+                    .add(offset)
+                    .cast::<libc::cmsghdr>()
+                    .read_unaligned()
+            };
+
+            if header.cmsg_len < header_length(0) {
+                break;
+            }
+
+            let end = buffer.len().min(offset + header.cmsg_len);
+            let data = &buffer[offset + header_length(0)..end];
+
+            // SAFETY: the caller guarantees the data to be valid for the type
+            messages.push(unsafe { Self::decode(header.cmsg_level, header.cmsg_type, data) });
+            offset += space(header.cmsg_len - header_length(0));
+        }
+
+        messages
+    }
+
+    unsafe fn decode(level: libc::c_int, kind: libc::c_int, data: &[u8]) -> Self {
+        // SAFETY: the caller guarantees the data to be valid for the type
+        unsafe {
+            match (level, kind) {
+                (libc::SOL_SOCKET, libc::SCM_RIGHTS) => Self::Rights(
+                    read_all::<RawFd>(data)
+                        .map(|file| OwnedFd::from_raw_fd(file))
+                        .collect(),
+                ),
+                (libc::SOL_SOCKET, libc::SCM_CREDENTIALS) if fits::<libc::ucred>(data) => {
+                    Self::Credentials(read(data))
+                }
+                (libc::IPPROTO_IP, libc::IP_PKTINFO) if fits::<libc::in_pktinfo>(data) => {
+                    Self::PacketInfo(read(data))
+                }
+                (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) if fits::<libc::in6_pktinfo>(data) => {
+                    Self::PacketInfoV6(read(data))
+                }
+                (libc::SOL_UDP, libc::UDP_GRO) if fits::<libc::c_int>(data) => {
+                    Self::UdpGro(u16::try_from(read::<libc::c_int>(data)).unwrap_or(u16::MAX))
+                }
+                (libc::SOL_SOCKET, libc::SCM_TIMESTAMP) if fits::<libc::timeval>(data) => {
+                    Self::Timestamp(read(data))
+                }
+                (libc::SOL_SOCKET, libc::SCM_TIMESTAMPNS) if fits::<libc::timespec>(data) => {
+                    Self::TimestampNs(read(data))
+                }
+                _ => Self::Other {
+                    level,
+                    kind,
+                    data: data.to_vec(),
+                },
+            }
+        }
+    }
+}
+
+/// Length of a control message header followed by the data
+fn header_length(data: usize) -> usize {
+    // SAFETY: only does arithmetic
+    unsafe { libc::CMSG_LEN(u32::try_from(data).unwrap()) as usize }
+}
+
+/// Length of a control message including padding up to the next one
+fn space(data: usize) -> usize {
+    // SAFETY: only does arithmetic
+    unsafe { libc::CMSG_SPACE(u32::try_from(data).unwrap()) as usize }
+}
+
+const fn fits<T>(data: &[u8]) -> bool {
+    data.len() >= std::mem::size_of::<T>()
+}
+
+const unsafe fn read<T: Copy>(data: &[u8]) -> T {
+    // SAFETY: the caller checks the length and validity
+    unsafe { data.as_ptr().cast::<T>().read_unaligned() }
+}
+
+unsafe fn read_all<T: Copy>(data: &[u8]) -> impl Iterator<Item = T> + '_ {
+    data.chunks_exact(std::mem::size_of::<T>())
+        // SAFETY: the caller guarantees validity and the chunk length is exact
+        .map(|chunk| unsafe { read(chunk) })
+}
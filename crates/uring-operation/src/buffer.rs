@@ -0,0 +1,176 @@
+use std::{
+    alloc::Layout,
+    cell::Cell,
+    io::Result,
+    ops::Deref,
+    ptr::NonNull,
+    sync::atomic::{AtomicU16, Ordering},
+};
+
+use io_uring::types::BufRingEntry;
+use uring_reactor::Reactor;
+
+/// Ring of buffers handed to the kernel for operations to select from when
+/// data arrives, instead of committing a buffer up front
+#[must_use]
+pub struct BufferRing<'a> {
+    reactor: &'a Reactor,
+    group: u16,
+    entries: u16,
+    size: u32,
+    ring: NonNull<BufRingEntry>,
+    storage: NonNull<u8>,
+    tail: Cell<u16>,
+}
+
+impl<'a> BufferRing<'a> {
+    /// Allocate and register a ring of `entries` buffers with `size` bytes each
+    ///
+    /// # Panics
+    ///
+    /// If the amount of entries isn't a power of two, the size is zero or the
+    /// allocation fails
+    ///
+    /// # Errors
+    ///
+    /// If the kernel rejects the registration
+    pub fn new(reactor: &'a Reactor, group: u16, entries: u16, size: u32) -> Result<Self> {
+        assert!(entries.is_power_of_two(), "entries must be a power of two");
+        assert!(size > 0, "buffer size must be non-zero");
+
+        // SAFETY: allocation sizes are non-zero as asserted above and the ring
+        // is zeroed as required by the kernel
+        let (ring, storage) = unsafe {
+            let ring = std::alloc::alloc_zeroed(Self::ring_layout(entries));
+            let storage = std::alloc::alloc(Self::storage_layout(entries, size));
+
+            (
+                NonNull::new(ring.cast()).unwrap(),
+                NonNull::new(storage).unwrap(),
+            )
+        };
+
+        let this = Self {
+            reactor,
+            group,
+            entries,
+            size,
+            ring,
+            storage,
+            tail: Cell::new(0),
+        };
+
+        // SAFETY: the ring is owned and only freed after unregistering
+        unsafe {
+            reactor.register_buffer_ring(ring.as_ptr() as u64, entries, group)?;
+        }
+
+        for index in 0..entries {
+            this.provide(index);
+        }
+
+        Ok(this)
+    }
+
+    pub const fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Take ownership of a buffer selected by the kernel, which is handed back
+    /// to the kernel once dropped
+    ///
+    /// # Safety
+    ///
+    /// The index and length must come from a completion that selected the
+    /// buffer from this ring
+    pub const unsafe fn take(&self, index: u16, length: usize) -> ProvidedBuffer<'_, 'a> {
+        ProvidedBuffer {
+            ring: self,
+            index,
+            length,
+        }
+    }
+
+    fn ring_layout(entries: u16) -> Layout {
+        // SAFETY: querying the page size has no preconditions
+        let page = usize::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).unwrap();
+
+        Layout::array::<BufRingEntry>(entries.into())
+            .and_then(|layout| layout.align_to(page))
+            .unwrap()
+    }
+
+    fn storage_layout(entries: u16, size: u32) -> Layout {
+        Layout::array::<u8>(usize::from(entries) * usize::try_from(size).unwrap()).unwrap()
+    }
+
+    fn buffer(&self, index: u16) -> *mut u8 {
+        // SAFETY: indices are bounded by the amount of entries
+        unsafe {
+            self.storage
+                .as_ptr()
+                .add(usize::from(index) * usize::try_from(self.size).unwrap())
+        }
+    }
+
+    /// Hand a buffer to the kernel
+    fn provide(&self, index: u16) {
+        let tail = self.tail.get();
+
+        // SAFETY: the slot is bounded by the ring's mask and the tail is
+        // shared with the kernel only through atomic accesses
+        unsafe {
+            let entry = &mut *self
+                .ring
+                .as_ptr()
+                .add(usize::from(tail & (self.entries - 1)));
+
+            entry.set_addr(self.buffer(index) as u64);
+            entry.set_len(self.size);
+            entry.set_bid(index);
+
+            AtomicU16::from_ptr(BufRingEntry::tail(self.ring.as_ptr()).cast_mut())
+                .store(tail.wrapping_add(1), Ordering::Release);
+        }
+
+        self.tail.set(tail.wrapping_add(1));
+    }
+}
+
+impl Drop for BufferRing<'_> {
+    fn drop(&mut self) {
+        _ = self.reactor.unregister_buffer_ring(self.group);
+
+        // SAFETY: the kernel no longer references the memory
+        unsafe {
+            std::alloc::dealloc(self.ring.as_ptr().cast(), Self::ring_layout(self.entries));
+            std::alloc::dealloc(
+                self.storage.as_ptr(),
+                Self::storage_layout(self.entries, self.size),
+            );
+        }
+    }
+}
+
+/// Buffer selected by the kernel from a [`BufferRing`]
+pub struct ProvidedBuffer<'r, 'a> {
+    ring: &'r BufferRing<'a>,
+    index: u16,
+    length: usize,
+}
+
+impl Deref for ProvidedBuffer<'_, '_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the kernel filled the buffer up to the length and doesn't
+        // touch it until it's provided again
+        unsafe { std::slice::from_raw_parts(self.ring.buffer(self.index), self.length) }
+    }
+}
+
+impl Drop for ProvidedBuffer<'_, '_> {
+    fn drop(&mut self) {
+        self.ring.provide(self.index);
+    }
+}
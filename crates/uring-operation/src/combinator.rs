@@ -0,0 +1,381 @@
+use std::{
+    future::Future,
+    io::{Error, Result},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use io_uring::{cqueue, squeue};
+use uring_reactor::Reactor;
+
+use crate::{
+    common::Cancel,
+    error::OperationError,
+    operation::{MultishotOperation, Oneshot, OneshotOperation, Operation, RestartPolicy},
+};
+
+/// Combinators for composing operations without writing new implementations
+pub trait OperationExt: Operation {
+    /// Transform the output of every completion
+    fn map<F, T>(self, map: F) -> Map<Self, F>
+    where
+        F: FnMut(Self::Output) -> T,
+    {
+        Map {
+            operation: self,
+            map,
+        }
+    }
+
+    /// Submit another operation built from the output once this one
+    /// succeeded, which only applies to oneshot submissions
+    fn and_then<F, B>(self, next: F) -> AndThen<Self, B, F>
+    where
+        Self: OneshotOperation,
+        F: FnOnce(Self::Output) -> B,
+        B: Operation + OneshotOperation,
+    {
+        AndThen {
+            stage: Stage::First { operation: self },
+            next: Some(next),
+            failure: None,
+        }
+    }
+
+    /// Submit the operation again as long as it fails with errors matching
+    /// the predicate, such as `EAGAIN` or `EINTR`, which only applies to
+    /// oneshot submissions
+    fn retry_on<P>(self, predicate: P) -> RetryOn<Self, P>
+    where
+        Self: OneshotOperation,
+        P: FnMut(&Error) -> bool,
+    {
+        RetryOn {
+            operation: self,
+            predicate,
+        }
+    }
+}
+
+impl<O> OperationExt for O where O: Operation {}
+
+pin_project_lite::pin_project! {
+    /// Operation with transformed output created by [`OperationExt::map`]
+    #[must_use]
+    pub struct Map<O, F> {
+        #[pin]
+        operation: O,
+        map: F,
+    }
+}
+
+// SAFETY: forwards to the inner operation
+unsafe impl<O, F, T> Operation for Map<O, F>
+where
+    O: Operation,
+    F: FnMut(O::Output) -> T,
+{
+    type Output = T;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        self.project().operation.build_submission()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let this = self.project();
+
+        // SAFETY: the caller guarantees correspondence
+        unsafe { this.operation.process_completion(entry) }.map(this.map)
+    }
+
+    fn retry_after(self: Pin<&mut Self>, entry: &cqueue::Entry) -> bool {
+        self.project().operation.retry_after(entry)
+    }
+
+    fn restart_policy(&self) -> RestartPolicy {
+        self.operation.restart_policy()
+    }
+
+    fn restartable(&self) -> bool {
+        self.operation.restartable()
+    }
+
+    fn detach_resources(self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        self.project().operation.detach_resources()
+    }
+}
+
+impl<O, F> OneshotOperation for Map<O, F> where O: OneshotOperation {}
+
+impl<O, F> MultishotOperation for Map<O, F> where O: MultishotOperation {}
+
+pin_project_lite::pin_project! {
+    #[project = StageProjection]
+    enum Stage<A, B> {
+        First {
+            #[pin]
+            operation: A,
+        },
+        Second {
+            #[pin]
+            operation: B,
+        },
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// Sequence of two operations created by [`OperationExt::and_then`]
+    #[must_use]
+    pub struct AndThen<A, B, F> {
+        #[pin]
+        stage: Stage<A, B>,
+        next: Option<F>,
+        failure: Option<Error>,
+    }
+}
+
+// SAFETY: forwards to the inner operations, only replacing the first one
+// after it completed
+unsafe impl<A, B, F> Operation for AndThen<A, B, F>
+where
+    A: Operation,
+    B: Operation,
+    F: FnOnce(A::Output) -> B,
+{
+    type Output = B::Output;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        match self.project().stage.project() {
+            StageProjection::First { operation } => operation.build_submission(),
+            StageProjection::Second { operation } => operation.build_submission(),
+        }
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let this = self.project();
+        if let Some(error) = this.failure.take() {
+            return Err(error);
+        }
+
+        match this.stage.project() {
+            // SAFETY: the caller guarantees correspondence
+            StageProjection::Second { operation } => unsafe { operation.process_completion(entry) },
+            // the first operation's completion is consumed by `retry_after`,
+            // which only multishot submissions skip
+            StageProjection::First { .. } => unreachable!("chained operations are oneshot only"),
+        }
+    }
+
+    fn retry_after(self: Pin<&mut Self>, entry: &cqueue::Entry) -> bool {
+        let mut this = self.project();
+
+        match this.stage.as_mut().project() {
+            StageProjection::Second { operation } => operation.retry_after(entry),
+            StageProjection::First { mut operation } => {
+                if operation.as_mut().retry_after(entry) {
+                    return true;
+                }
+
+                // SAFETY: the entry completes the submission of the first
+                // operation
+                match unsafe { operation.process_completion(entry.clone()) } {
+                    Ok(output) => {
+                        let next = this.next.take().unwrap();
+                        this.stage.set(Stage::Second {
+                            operation: next(output),
+                        });
+
+                        true
+                    }
+                    Err(error) => {
+                        *this.failure = Some(error);
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+    fn detach_resources(self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        match self.project().stage.project() {
+            StageProjection::First { operation } => operation.detach_resources(),
+            StageProjection::Second { operation } => operation.detach_resources(),
+        }
+    }
+}
+
+// chained operations are deliberately not multishot, as streams don't consult
+// `retry_after` to move on to the second operation
+impl<A, B, F> OneshotOperation for AndThen<A, B, F>
+where
+    A: OneshotOperation,
+    B: OneshotOperation,
+{
+}
+
+pin_project_lite::pin_project! {
+    /// Operation resubmitted on matching errors created by
+    /// [`OperationExt::retry_on`]
+    #[must_use]
+    pub struct RetryOn<O, P> {
+        #[pin]
+        operation: O,
+        predicate: P,
+    }
+}
+
+// SAFETY: forwards to the inner operation
+unsafe impl<O, P> Operation for RetryOn<O, P>
+where
+    O: Operation,
+    P: FnMut(&Error) -> bool,
+{
+    type Output = O::Output;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        self.project().operation.build_submission()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        // SAFETY: the caller guarantees correspondence
+        unsafe { self.project().operation.process_completion(entry) }
+    }
+
+    fn retry_after(self: Pin<&mut Self>, entry: &cqueue::Entry) -> bool {
+        let this = self.project();
+
+        this.operation.retry_after(entry)
+            || (entry.result().is_negative()
+                && (this.predicate)(&Error::from_raw_os_error(-entry.result())))
+    }
+
+    fn detach_resources(self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        self.project().operation.detach_resources()
+    }
+}
+
+// retries are only driven by oneshot submissions
+impl<O, P> OneshotOperation for RetryOn<O, P> where O: OneshotOperation {}
+
+/// Outcome of a [`Race`], telling which operation completed first
+#[derive(Debug)]
+pub enum Raced<A, B> {
+    First(A),
+    Second(B),
+}
+
+/// Submit two operations and resolve to whichever completes first, cancelling
+/// the other one
+pub fn race<A, B, S, C>(reactor: &Reactor<S, C>, first: A, second: B) -> Race<'_, A, B, S, C>
+where
+    A: Operation<S, C> + OneshotOperation,
+    B: Operation<S, C> + OneshotOperation,
+    Cancel: Operation<S, C, Output = ()>,
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+{
+    Race {
+        reactor,
+        first: first.submit_oneshot(reactor),
+        second: second.submit_oneshot(reactor),
+        winner: None,
+        cancel: None,
+        loser_settled: false,
+        cancel_settled: false,
+    }
+}
+
+/// Output of a completed operation or the error describing its failure
+type Completion<O, S, C> = std::result::Result<<O as Operation<S, C>>::Output, OperationError>;
+
+pin_project_lite::pin_project! {
+    /// Future created by [`race`], which only resolves after the loser's
+    /// completion arrived so that its resources stay alive until then
+    #[must_use]
+    pub struct Race<'a, A, B, S = squeue::Entry, C = cqueue::Entry>
+    where
+        A: Operation<S, C>,
+        B: Operation<S, C>,
+        Cancel: Operation<S, C, Output = ()>,
+        S: squeue::EntryMarker,
+        C: cqueue::EntryMarker,
+    {
+        reactor: &'a Reactor<S, C>,
+        #[pin]
+        first: Oneshot<'a, A, S, C>,
+        #[pin]
+        second: Oneshot<'a, B, S, C>,
+        winner: Option<Raced<Completion<A, S, C>, Completion<B, S, C>>>,
+        cancel: Option<Oneshot<'a, Cancel, S, C>>,
+        loser_settled: bool,
+        cancel_settled: bool,
+    }
+}
+
+impl<A, B, S, C> Future for Race<'_, A, B, S, C>
+where
+    A: Operation<S, C>,
+    B: Operation<S, C>,
+    Cancel: Operation<S, C, Output = ()>,
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+{
+    type Output = Raced<Completion<A, S, C>, Completion<B, S, C>>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if this.winner.is_none() {
+            if let Poll::Ready(result) = this.first.as_mut().poll(context) {
+                *this.winner = Some(Raced::First(result));
+            } else if let Poll::Ready(result) = this.second.as_mut().poll(context) {
+                *this.winner = Some(Raced::Second(result));
+            } else {
+                return Poll::Pending;
+            }
+        }
+
+        let handle = match this.winner {
+            Some(Raced::First(_)) => this.second.operation_handle(),
+            _ => this.first.operation_handle(),
+        };
+
+        // the loser never got submitted if the winner failed right away
+        let Some(handle) = handle else {
+            return Poll::Ready(this.winner.take().unwrap());
+        };
+
+        let cancel = this
+            .cancel
+            .get_or_insert_with(|| Cancel::new(handle).submit_oneshot(this.reactor));
+
+        // the cancellation may fail if the loser completed in the meantime
+        if !*this.cancel_settled && Pin::new(cancel).poll(context).is_ready() {
+            *this.cancel_settled = true;
+        }
+
+        if !*this.loser_settled {
+            let settled = match this.winner {
+                Some(Raced::First(_)) => this.second.poll(context).is_ready(),
+                _ => this.first.poll(context).is_ready(),
+            };
+
+            *this.loser_settled = settled;
+        }
+
+        if *this.loser_settled && *this.cancel_settled {
+            return Poll::Ready(this.winner.take().unwrap());
+        }
+
+        Poll::Pending
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    io::{Error, Result},
+    os::fd::{AsRawFd, BorrowedFd},
+    pin::Pin,
+};
+
+use io_uring::{cqueue, opcode, squeue, types::Fd};
+
+use crate::operation::{MultishotOperation, OneshotOperation, Operation};
+
+/// Socket command reporting the amount of unread bytes in the receive queue
+const SOCKET_URING_OP_SIOCINQ: u32 = 0;
+
+/// Socket command reporting the amount of unsent bytes in the send queue
+const SOCKET_URING_OP_SIOCOUTQ: u32 = 1;
+
+/// Pass a driver specific command with up to 16 bytes of payload through to
+/// the file, producing the driver's result
+#[must_use]
+pub struct UringCmd<'a> {
+    file: BorrowedFd<'a>,
+    command: u32,
+    payload: [u8; 16],
+}
+
+impl<'a> UringCmd<'a> {
+    pub const fn new(file: BorrowedFd<'a>, command: u32) -> Self {
+        Self {
+            file,
+            command,
+            payload: [0; 16],
+        }
+    }
+
+    /// Query the amount of unread bytes queued on a socket
+    ///
+    /// Requires Linux 6.7 or newer
+    pub const fn socket_input_queue(socket: BorrowedFd<'a>) -> Self {
+        Self::new(socket, SOCKET_URING_OP_SIOCINQ)
+    }
+
+    /// Query the amount of unsent bytes queued on a socket
+    ///
+    /// Requires Linux 6.7 or newer
+    pub const fn socket_output_queue(socket: BorrowedFd<'a>) -> Self {
+        Self::new(socket, SOCKET_URING_OP_SIOCOUTQ)
+    }
+
+    pub const fn payload(mut self, payload: [u8; 16]) -> Self {
+        self.payload = payload;
+        self
+    }
+}
+
+// SAFETY: file bound to live long enough and the payload is copied into the
+// submission
+unsafe impl Operation for UringCmd<'_> {
+    type Output = u32;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::UringCmd16::new(Fd(self.file.as_raw_fd()), self.command)
+            .cmd(self.payload)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(entry.result().cast_unsigned())
+    }
+}
+
+impl OneshotOperation for UringCmd<'_> {}
+
+impl MultishotOperation for UringCmd<'_> {}
+
+/// Pass a driver specific command with up to 80 bytes of payload through to
+/// the file, such as `NVMe` passthrough, producing the driver's result along
+/// with the extra completion data
+///
+/// Only available on reactors with big submission and completion entries
+#[must_use]
+pub struct UringCmd80<'a> {
+    file: BorrowedFd<'a>,
+    command: u32,
+    payload: [u8; 80],
+}
+
+impl<'a> UringCmd80<'a> {
+    pub const fn new(file: BorrowedFd<'a>, command: u32) -> Self {
+        Self {
+            file,
+            command,
+            payload: [0; 80],
+        }
+    }
+
+    pub const fn payload(mut self, payload: [u8; 80]) -> Self {
+        self.payload = payload;
+        self
+    }
+}
+
+// SAFETY: file bound to live long enough and the payload is copied into the
+// submission
+unsafe impl Operation<squeue::Entry128, cqueue::Entry32> for UringCmd80<'_> {
+    type Output = (u32, [u64; 2]);
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry128 {
+        opcode::UringCmd80::new(Fd(self.file.as_raw_fd()), self.command)
+            .cmd(self.payload)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry32,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok((entry.result().cast_unsigned(), *entry.big_cqe()))
+    }
+}
+
+impl OneshotOperation for UringCmd80<'_> {}
+
+impl MultishotOperation for UringCmd80<'_> {}
@@ -1,13 +1,49 @@
 use std::{
-    io::{Error, Result},
-    os::fd::{IntoRawFd, OwnedFd},
+    io::{Error, ErrorKind, Result},
+    ops::Range,
+    os::fd::{FromRawFd, IntoRawFd, OwnedFd},
     pin::Pin,
 };
 
 use io_uring::{cqueue, opcode, squeue, types::Fd};
 use uring_reactor::OperationId;
 
-use crate::operation::Operation;
+use crate::operation::{MultishotOperation, OneshotOperation, Operation};
+
+/// Kernel flag for completing a no-op with the result from the length field
+const IORING_NOP_INJECT_RESULT: u32 = 1 << 0;
+
+/// Byte range of the `len` field in the kernel's `io_uring_sqe`
+const LENGTH_FIELD: Range<usize> = 24..28;
+
+/// Byte range of the union of operation flags in the kernel's `io_uring_sqe`,
+/// such as `rw_flags`, `poll32_events` or `nop_flags`
+const OPERATION_FLAGS_FIELD: Range<usize> = 28..32;
+
+const _: () = assert!(size_of::<squeue::Entry>() == 64);
+
+/// Overwrite the length and operation flags of an entry, for operations that
+/// `io-uring` has no builder for, since its raw submission structure isn't
+/// public
+pub fn patch_entry(entry: squeue::Entry, length: u32, flags: u32) -> squeue::Entry {
+    // SAFETY: entries are `repr(C)` wrappers of the kernel's submission
+    // structure, which is 64 bytes as asserted above and for which any bytes
+    // are valid
+    let mut raw = unsafe { std::mem::transmute::<squeue::Entry, [u8; 64]>(entry) };
+    raw[LENGTH_FIELD].copy_from_slice(&length.to_ne_bytes());
+    raw[OPERATION_FLAGS_FIELD].copy_from_slice(&flags.to_ne_bytes());
+
+    // SAFETY: see above
+    unsafe { std::mem::transmute::<[u8; 64], squeue::Entry>(raw) }
+}
+
+/// Close a descriptor created by an operation that nobody waits for anymore
+pub fn close_created(entry: &cqueue::Entry) {
+    if entry.result() >= 0 {
+        // SAFETY: the kernel handed us a new descriptor that nobody else owns
+        drop(unsafe { OwnedFd::from_raw_fd(entry.result()) });
+    }
+}
 
 #[must_use]
 pub struct Raw {
@@ -40,6 +76,68 @@ unsafe impl Operation for Raw {
     }
 }
 
+impl OneshotOperation for Raw {}
+
+impl MultishotOperation for Raw {}
+
+/// Complete without doing anything, mostly useful for measuring overhead
+#[must_use]
+pub struct Nop {
+    result: Option<i32>,
+}
+
+impl Nop {
+    pub const fn new() -> Self {
+        Self { result: None }
+    }
+
+    /// Complete with the given result instead of zero, where negative values
+    /// are reported as errors
+    ///
+    /// Requires Linux 6.10 or newer
+    pub const fn inject_result(mut self, result: i32) -> Self {
+        self.result = Some(result);
+        self
+    }
+}
+
+impl Default for Nop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SAFETY: no parameters that could get invalidated
+unsafe impl Operation for Nop {
+    type Output = u32;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let entry = opcode::Nop::new().build();
+        let Some(result) = self.result else {
+            return entry;
+        };
+
+        // `io-uring` lacks a builder for injected results, which are taken
+        // from the length field when enabled in the operation flags
+        patch_entry(entry, result.cast_unsigned(), IORING_NOP_INJECT_RESULT)
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(entry.result().cast_unsigned())
+    }
+}
+
+impl OneshotOperation for Nop {}
+
+impl MultishotOperation for Nop {}
+
 #[must_use]
 pub struct Cancel {
     operation: OperationId,
@@ -71,14 +169,24 @@ unsafe impl Operation for Cancel {
     }
 }
 
+impl OneshotOperation for Cancel {}
+
+impl MultishotOperation for Cancel {}
+
+/// Close the file, which the kernel releases even when closing fails, so that
+/// submitting it again only fails
 #[must_use]
 pub struct Close {
     file: Option<OwnedFd>,
+    resubmitted: bool,
 }
 
 impl Close {
     pub const fn new(file: OwnedFd) -> Self {
-        Self { file: Some(file) }
+        Self {
+            file: Some(file),
+            resubmitted: false,
+        }
     }
 }
 
@@ -87,13 +195,27 @@ unsafe impl Operation for Close {
     type Output = ();
 
     fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
-        opcode::Close::new(Fd(self.file.take().unwrap().into_raw_fd())).build()
+        // completes with an error without touching the descriptor, which may
+        // already belong to another file
+        let Some(file) = self.file.take() else {
+            self.resubmitted = true;
+            return opcode::Nop::new().build();
+        };
+
+        opcode::Close::new(Fd(file.into_raw_fd())).build()
     }
 
     unsafe fn process_completion(
         self: Pin<&mut Self>,
         entry: cqueue::Entry,
     ) -> Result<Self::Output> {
+        if self.resubmitted {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "file was already closed",
+            ));
+        }
+
         if entry.result().is_negative() {
             return Err(Error::from_raw_os_error(-entry.result()));
         }
@@ -101,3 +223,33 @@ unsafe impl Operation for Close {
         Ok(())
     }
 }
+
+impl OneshotOperation for Close {}
+
+#[cfg(test)]
+mod tests {
+    use io_uring::{opcode, squeue, types::Fd};
+
+    use super::patch_entry;
+
+    fn bytes(entry: squeue::Entry) -> [u8; 64] {
+        // SAFETY: see `patch_entry`
+        unsafe { std::mem::transmute::<squeue::Entry, [u8; 64]>(entry) }
+    }
+
+    /// The patched fields must land where `io-uring` puts the length and
+    /// operation flags of operations it has builders for
+    #[test]
+    fn patched_fields_match_builders() {
+        let buffer = [0_u8; 16];
+        let unpatched = opcode::Read::new(Fd(3), buffer.as_ptr().cast_mut(), 0).build();
+        let built = opcode::Read::new(Fd(3), buffer.as_ptr().cast_mut(), 0x1234_5678)
+            .rw_flags(0x0765_4321)
+            .build();
+
+        assert_eq!(
+            bytes(patch_entry(unpatched, 0x1234_5678, 0x0765_4321)),
+            bytes(built)
+        );
+    }
+}
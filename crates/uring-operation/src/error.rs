@@ -0,0 +1,259 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::Error,
+    os::fd::RawFd,
+};
+
+use io_uring::{opcode, squeue};
+use uring_reactor::OperationId;
+
+/// Submission flag marking the descriptor as an index into registered files
+const IOSQE_FIXED_FILE: u8 = 1 << 0;
+
+/// Distinguished reasons for an operation failing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OperationErrorKind {
+    /// The operation was cancelled before it could complete
+    Cancelled,
+    /// The operation or its linked timeout expired
+    TimedOut,
+    /// The operation couldn't be submitted because the ring was full
+    RingFull,
+    /// Any other failure reported by the kernel
+    Other,
+}
+
+/// Failure of an operation along with which operation failed on what file
+#[derive(Debug)]
+pub struct OperationError {
+    opcode: u8,
+    file: Option<RawFd>,
+    handle: Option<OperationId>,
+    source: Error,
+}
+
+impl OperationError {
+    /// Attach context to an error of an operation with the given opcode,
+    /// leaving out the handle if it never got submitted
+    #[must_use]
+    pub const fn new(
+        opcode: u8,
+        file: Option<RawFd>,
+        handle: Option<OperationId>,
+        source: Error,
+    ) -> Self {
+        Self {
+            opcode,
+            file,
+            handle,
+            source,
+        }
+    }
+
+    #[must_use]
+    pub const fn opcode(&self) -> u8 {
+        self.opcode
+    }
+
+    /// Name of the opcode as used in the kernel's `IORING_OP_*` constants
+    #[must_use]
+    pub const fn opcode_name(&self) -> &'static str {
+        opcode_name(self.opcode)
+    }
+
+    /// Descriptor the operation worked on, if it's not a registered file
+    #[must_use]
+    pub const fn file(&self) -> Option<RawFd> {
+        self.file
+    }
+
+    #[must_use]
+    pub const fn operation_handle(&self) -> Option<OperationId> {
+        self.handle
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> OperationErrorKind {
+        if matches!(self.source.get_ref(), Some(inner) if inner.is::<squeue::PushError>()) {
+            return OperationErrorKind::RingFull;
+        }
+
+        match self.source.raw_os_error() {
+            Some(libc::ECANCELED) => OperationErrorKind::Cancelled,
+            Some(libc::ETIME | libc::ETIMEDOUT) => OperationErrorKind::TimedOut,
+            Some(libc::EBUSY) if self.handle.is_none() => OperationErrorKind::RingFull,
+            _ => OperationErrorKind::Other,
+        }
+    }
+
+    #[must_use]
+    pub const fn io_error(&self) -> &Error {
+        &self.source
+    }
+
+    #[must_use]
+    pub fn into_io_error(self) -> Error {
+        self.source
+    }
+}
+
+impl Display for OperationError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.opcode_name())?;
+
+        if let Some(file) = self.file {
+            write!(formatter, " on fd {file}")?;
+        }
+
+        if let Some(handle) = self.handle {
+            write!(formatter, " (operation {})", handle.as_raw())?;
+        }
+
+        write!(formatter, " failed: {}", self.source)
+    }
+}
+
+impl std::error::Error for OperationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        // the underlying error is already part of the message
+        self.source.source()
+    }
+}
+
+impl From<OperationError> for Error {
+    fn from(error: OperationError) -> Self {
+        Self::new(error.source.kind(), error)
+    }
+}
+
+/// Opcode and file of a built submission, kept around to describe failures
+#[derive(Clone, Copy)]
+pub struct Origin {
+    opcode: u8,
+    file: Option<RawFd>,
+}
+
+impl Origin {
+    /// Placeholder until the first submission is built
+    pub const UNKNOWN: Self = Self {
+        opcode: u8::MAX,
+        file: None,
+    };
+
+    pub fn of<S: squeue::EntryMarker>(entry: &S) -> Self {
+        // SAFETY: both entry sizes are `repr(C)` and start with the kernel's
+        // submission structure, which has the opcode and flags in the first
+        // two bytes and the descriptor at offset four
+        let (opcode, flags, file) = unsafe {
+            let raw = std::ptr::from_ref(entry).cast::<u8>();
+            (
+                *raw,
+                *raw.add(1),
+                raw.add(4).cast::<RawFd>().read_unaligned(),
+            )
+        };
+
+        Self {
+            opcode,
+            file: (flags & IOSQE_FIXED_FILE == 0 && uses_file(opcode)).then_some(file),
+        }
+    }
+
+    pub const fn error(self, handle: Option<OperationId>, source: Error) -> OperationError {
+        OperationError::new(self.opcode, self.file, handle, source)
+    }
+}
+
+/// Whether the descriptor field of the opcode refers to a file
+const fn uses_file(code: u8) -> bool {
+    !matches!(
+        code,
+        opcode::Nop::CODE
+            | opcode::PollRemove::CODE
+            | opcode::Timeout::CODE
+            | opcode::TimeoutRemove::CODE
+            | opcode::AsyncCancel::CODE
+            | opcode::LinkTimeout::CODE
+            | opcode::FilesUpdate::CODE
+            | opcode::ProvideBuffers::CODE
+            | opcode::RemoveBuffers::CODE
+            | opcode::GetXattr::CODE
+            | opcode::SetXattr::CODE
+            | opcode::Socket::CODE
+            | opcode::FutexWait::CODE
+            | opcode::FutexWake::CODE
+            | opcode::FutexWaitV::CODE
+            | opcode::WaitId::CODE
+            | opcode::Pipe::CODE
+    )
+}
+
+const fn opcode_name(code: u8) -> &'static str {
+    match code {
+        opcode::Nop::CODE => "nop",
+        opcode::Readv::CODE => "readv",
+        opcode::Writev::CODE => "writev",
+        opcode::Fsync::CODE => "fsync",
+        opcode::ReadFixed::CODE => "read_fixed",
+        opcode::WriteFixed::CODE => "write_fixed",
+        opcode::PollAdd::CODE => "poll_add",
+        opcode::PollRemove::CODE => "poll_remove",
+        opcode::SyncFileRange::CODE => "sync_file_range",
+        opcode::SendMsg::CODE => "sendmsg",
+        opcode::RecvMsg::CODE => "recvmsg",
+        opcode::Timeout::CODE => "timeout",
+        opcode::TimeoutRemove::CODE => "timeout_remove",
+        opcode::Accept::CODE => "accept",
+        opcode::AsyncCancel::CODE => "async_cancel",
+        opcode::LinkTimeout::CODE => "link_timeout",
+        opcode::Connect::CODE => "connect",
+        opcode::Fallocate::CODE => "fallocate",
+        opcode::OpenAt::CODE => "openat",
+        opcode::Close::CODE => "close",
+        opcode::FilesUpdate::CODE => "files_update",
+        opcode::Statx::CODE => "statx",
+        opcode::Read::CODE => "read",
+        opcode::Write::CODE => "write",
+        opcode::Fadvise::CODE => "fadvise",
+        opcode::Madvise::CODE => "madvise",
+        opcode::Send::CODE => "send",
+        opcode::Recv::CODE => "recv",
+        opcode::OpenAt2::CODE => "openat2",
+        opcode::EpollCtl::CODE => "epoll_ctl",
+        opcode::Splice::CODE => "splice",
+        opcode::ProvideBuffers::CODE => "provide_buffers",
+        opcode::RemoveBuffers::CODE => "remove_buffers",
+        opcode::Tee::CODE => "tee",
+        opcode::Shutdown::CODE => "shutdown",
+        opcode::RenameAt::CODE => "renameat",
+        opcode::UnlinkAt::CODE => "unlinkat",
+        opcode::MkDirAt::CODE => "mkdirat",
+        opcode::SymlinkAt::CODE => "symlinkat",
+        opcode::LinkAt::CODE => "linkat",
+        opcode::MsgRingData::CODE => "msg_ring",
+        opcode::FSetXattr::CODE => "fsetxattr",
+        opcode::SetXattr::CODE => "setxattr",
+        opcode::FGetXattr::CODE => "fgetxattr",
+        opcode::GetXattr::CODE => "getxattr",
+        opcode::Socket::CODE => "socket",
+        opcode::UringCmd16::CODE => "uring_cmd",
+        opcode::SendZc::CODE => "send_zc",
+        opcode::SendMsgZc::CODE => "sendmsg_zc",
+        opcode::ReadMulti::CODE => "read_multishot",
+        opcode::WaitId::CODE => "waitid",
+        opcode::FutexWait::CODE => "futex_wait",
+        opcode::FutexWake::CODE => "futex_wake",
+        opcode::FutexWaitV::CODE => "futex_waitv",
+        opcode::FixedFdInstall::CODE => "fixed_fd_install",
+        opcode::Ftruncate::CODE => "ftruncate",
+        opcode::Bind::CODE => "bind",
+        opcode::Listen::CODE => "listen",
+        opcode::RecvZc::CODE => "recv_zc",
+        opcode::EpollWait::CODE => "epoll_wait",
+        opcode::ReadvFixed::CODE => "readv_fixed",
+        opcode::WritevFixed::CODE => "writev_fixed",
+        opcode::Pipe::CODE => "pipe",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    ffi::CString,
+    io::{Error, Result},
+    mem::MaybeUninit,
+    os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+    pin::Pin,
+};
+
+use io_uring::{
+    cqueue,
+    opcode,
+    squeue,
+    types::{Fd, FsyncFlags},
+};
+use uring_reactor::keep_alive;
+
+use crate::{
+    common::close_created,
+    operation::{MultishotOperation, OneshotOperation, Operation},
+};
+
+/// Largest amount of bytes transferred by a single read or write, which is
+/// aligned for any direct I/O requirement
+const MAX_TRANSFER: usize = 1 << 30;
+
+/// Length field for a transfer limited to [`MAX_TRANSFER`]
+#[allow(clippy::cast_possible_truncation)]
+const fn transfer_length(length: usize) -> u32 {
+    if length > MAX_TRANSFER {
+        MAX_TRANSFER as u32
+    } else {
+        length as u32
+    }
+}
+
+/// Owned memory that the kernel reads from or writes into, which stays in
+/// place when the buffer itself is moved
+///
+/// # Safety
+///
+/// The pointers must be valid for the capacity and stay the same while the
+/// buffer isn't accessed, and the length must never exceed the capacity
+pub unsafe trait IoBuffer: Unpin + 'static {
+    /// Start of the memory
+    fn as_ptr(&self) -> *const u8;
+
+    /// Start of the memory, for writing into
+    fn as_mut_ptr(&mut self) -> *mut u8;
+
+    /// Amount of initialized bytes at the start
+    fn len(&self) -> usize;
+
+    /// Amount of bytes the memory holds
+    fn capacity(&self) -> usize;
+
+    /// Mark the given amount of bytes at the start as initialized
+    ///
+    /// # Safety
+    ///
+    /// The bytes must be initialized and fit the capacity
+    unsafe fn set_len(&mut self, length: usize);
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// SAFETY: the contents are on the heap and only move when growing
+unsafe impl IoBuffer for Vec<u8> {
+    fn as_ptr(&self) -> *const u8 {
+        self.as_slice().as_ptr()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.as_mut_slice().as_mut_ptr()
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    unsafe fn set_len(&mut self, length: usize) {
+        // SAFETY: the caller guarantees initialization
+        unsafe { self.set_len(length) };
+    }
+}
+
+/// Open the file at the path, relative to the working directory
+#[must_use]
+pub struct OpenAt {
+    path: CString,
+    flags: libc::c_int,
+    mode: libc::mode_t,
+}
+
+impl OpenAt {
+    pub const fn new(path: CString) -> Self {
+        Self {
+            path,
+            flags: libc::O_CLOEXEC,
+            mode: 0,
+        }
+    }
+
+    /// Flags as passed to `open(2)`, replacing the default `O_CLOEXEC`
+    pub const fn flags(mut self, flags: libc::c_int) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Permissions of a newly created file before applying the umask
+    pub const fn mode(mut self, mode: libc::mode_t) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+// SAFETY: path is owned
+unsafe impl Operation for OpenAt {
+    type Output = OwnedFd;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::OpenAt::new(Fd(libc::AT_FDCWD), self.path.as_ptr())
+            .flags(self.flags)
+            .mode(self.mode)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        // SAFETY: the kernel handed us a new file descriptor
+        Ok(unsafe { OwnedFd::from_raw_fd(entry.result()) })
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        let path = std::mem::take(&mut self.path);
+
+        Box::new(move |entry| {
+            let _ = &path;
+            close_created(&entry);
+        })
+    }
+}
+
+impl OneshotOperation for OpenAt {}
+
+impl MultishotOperation for OpenAt {}
+
+/// Query metadata of the file through `statx(2)`
+#[must_use]
+pub struct Statx<'a> {
+    file: BorrowedFd<'a>,
+    mask: u32,
+    result: Option<Box<MaybeUninit<libc::statx>>>,
+}
+
+impl<'a> Statx<'a> {
+    pub fn new(file: BorrowedFd<'a>, mask: u32) -> Self {
+        Self {
+            file,
+            mask,
+            result: Some(Box::new(MaybeUninit::zeroed())),
+        }
+    }
+}
+
+// SAFETY: file bound to live long enough, the result is owned and the path
+// is static
+unsafe impl Operation for Statx<'_> {
+    type Output = libc::statx;
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        let file = self.file.as_raw_fd();
+        let mask = self.mask;
+        let result = self.result.as_mut().unwrap();
+
+        opcode::Statx::new(Fd(file), c"".as_ptr(), result.as_mut_ptr().cast())
+            .flags(libc::AT_EMPTY_PATH)
+            .mask(mask)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        // SAFETY: zeroed and filled in by the kernel
+        Ok(unsafe { self.result.as_ref().unwrap().assume_init_read() })
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.result.take())
+    }
+}
+
+impl OneshotOperation for Statx<'_> {}
+
+impl MultishotOperation for Statx<'_> {}
+
+/// Flush contents and metadata of the file to the storage device
+#[must_use]
+pub struct Fsync<'a> {
+    file: BorrowedFd<'a>,
+    flags: FsyncFlags,
+}
+
+impl<'a> Fsync<'a> {
+    pub const fn new(file: BorrowedFd<'a>) -> Self {
+        Self {
+            file,
+            flags: FsyncFlags::empty(),
+        }
+    }
+
+    /// Only flush the metadata needed to read the contents back
+    pub const fn data_only(mut self) -> Self {
+        self.flags = FsyncFlags::DATASYNC;
+        self
+    }
+}
+
+// SAFETY: file bound to live long enough
+unsafe impl Operation for Fsync<'_> {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::Fsync::new(Fd(self.file.as_raw_fd()))
+            .flags(self.flags)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+}
+
+impl OneshotOperation for Fsync<'_> {}
+
+impl MultishotOperation for Fsync<'_> {}
+
+/// Truncate or extend the file to the given length, which requires Linux 6.9
+/// or newer
+#[must_use]
+pub struct Ftruncate<'a> {
+    file: BorrowedFd<'a>,
+    length: u64,
+}
+
+impl<'a> Ftruncate<'a> {
+    pub const fn new(file: BorrowedFd<'a>, length: u64) -> Self {
+        Self { file, length }
+    }
+}
+
+// SAFETY: file bound to live long enough
+unsafe impl Operation for Ftruncate<'_> {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::Ftruncate::new(Fd(self.file.as_raw_fd()), self.length).build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+}
+
+impl OneshotOperation for Ftruncate<'_> {}
+
+impl MultishotOperation for Ftruncate<'_> {}
+
+/// Read into the spare capacity of an owned buffer from the given offset,
+/// limited to a gigabyte at once
+#[must_use]
+pub struct ReadAt<'a, B> {
+    file: BorrowedFd<'a>,
+    buffer: Option<B>,
+    offset: u64,
+}
+
+impl<'a, B: IoBuffer> ReadAt<'a, B> {
+    pub const fn new(file: BorrowedFd<'a>, buffer: B, offset: u64) -> Self {
+        Self {
+            file,
+            buffer: Some(buffer),
+            offset,
+        }
+    }
+}
+
+// SAFETY: file bound to live long enough and the buffer is owned with its
+// memory staying in place
+unsafe impl<B: IoBuffer> Operation for ReadAt<'_, B> {
+    type Output = B;
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        let file = self.file.as_raw_fd();
+        let offset = self.offset;
+        let buffer = self.buffer.as_mut().unwrap();
+        let length = buffer.capacity() - buffer.len();
+
+        // SAFETY: the length is within the capacity
+        let start = unsafe { buffer.as_mut_ptr().add(buffer.len()) };
+
+        opcode::Read::new(Fd(file), start, transfer_length(length))
+            .offset(offset)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let amount: usize = entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))?;
+
+        let mut buffer = self.buffer.take().unwrap();
+
+        // SAFETY: we trust the kernel to tell us how much was read into the buffer
+        unsafe { buffer.set_len(buffer.len() + amount) };
+
+        Ok(buffer)
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.buffer.take())
+    }
+}
+
+impl<B: IoBuffer> OneshotOperation for ReadAt<'_, B> {}
+
+/// Write the contents of an owned buffer at the given offset, limited to a
+/// gigabyte at once, returning how much was written along with the buffer
+#[must_use]
+pub struct WriteAt<'a, B> {
+    file: BorrowedFd<'a>,
+    buffer: Option<B>,
+    position: usize,
+    offset: u64,
+}
+
+impl<'a, B: IoBuffer> WriteAt<'a, B> {
+    pub const fn new(file: BorrowedFd<'a>, buffer: B, offset: u64) -> Self {
+        Self {
+            file,
+            buffer: Some(buffer),
+            position: 0,
+            offset,
+        }
+    }
+
+    /// Skip the contents of the buffer before the given position
+    ///
+    /// # Panics
+    ///
+    /// If the position is past the contents
+    pub fn position(mut self, position: usize) -> Self {
+        assert!(
+            self.buffer
+                .as_ref()
+                .is_some_and(|buffer| position <= buffer.len()),
+            "position is past the buffer contents"
+        );
+
+        self.position = position;
+        self
+    }
+}
+
+// SAFETY: file bound to live long enough and the buffer is owned with its
+// memory staying in place
+unsafe impl<B: IoBuffer> Operation for WriteAt<'_, B> {
+    type Output = (usize, B);
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let buffer = self.buffer.as_ref().unwrap();
+
+        // SAFETY: the position is within the initialized contents
+        let start = unsafe { buffer.as_ptr().add(self.position) };
+
+        opcode::Write::new(
+            Fd(self.file.as_raw_fd()),
+            start,
+            transfer_length(buffer.len() - self.position),
+        )
+        .offset(self.offset)
+        .build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let amount = entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))?;
+
+        Ok((amount, self.buffer.take().unwrap()))
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.buffer.take())
+    }
+}
+
+impl<B: IoBuffer> OneshotOperation for WriteAt<'_, B> {}
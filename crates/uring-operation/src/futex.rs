@@ -0,0 +1,226 @@
+use std::{
+    io::{Error, Result},
+    marker::PhantomData,
+    pin::Pin,
+    sync::atomic::AtomicU32,
+};
+
+use io_uring::{cqueue, opcode, squeue, types::FutexWaitV as FutexVector};
+use uring_reactor::keep_alive;
+
+use crate::operation::{MultishotOperation, OneshotOperation, Operation};
+
+/// `futex2(2)` flag for 32 bit futexes
+const FUTEX2_SIZE_U32: u32 = 0x02;
+
+/// `futex2(2)` flag for futexes only shared within the process
+const FUTEX2_PRIVATE: u32 = 128;
+
+/// Mask matching any waiter or waker
+const FUTEX_BITSET_MATCH_ANY: u32 = u32::MAX;
+
+/// Wait until woken as long as the futex holds the expected value, which
+/// fails with `EAGAIN` otherwise
+///
+/// Requires Linux 6.7 or newer
+#[must_use]
+pub struct FutexWait<'a> {
+    futex: &'a AtomicU32,
+    expected: u32,
+    mask: u32,
+    flags: u32,
+}
+
+impl<'a> FutexWait<'a> {
+    pub const fn new(futex: &'a AtomicU32, expected: u32) -> Self {
+        Self {
+            futex,
+            expected,
+            mask: FUTEX_BITSET_MATCH_ANY,
+            flags: FUTEX2_SIZE_U32 | FUTEX2_PRIVATE,
+        }
+    }
+
+    /// Only get woken by wakers with overlapping masks
+    pub const fn mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Allow the futex to live in memory shared with other processes
+    pub const fn shared(mut self) -> Self {
+        self.flags &= !FUTEX2_PRIVATE;
+        self
+    }
+}
+
+// SAFETY: futex bound to live long enough
+unsafe impl Operation for FutexWait<'_> {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::FutexWait::new(
+            self.futex.as_ptr(),
+            self.expected.into(),
+            self.mask.into(),
+            self.flags,
+        )
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+}
+
+impl OneshotOperation for FutexWait<'_> {}
+
+impl MultishotOperation for FutexWait<'_> {}
+
+/// Wake up to the given amount of waiters, producing how many were woken
+///
+/// Requires Linux 6.7 or newer
+#[must_use]
+pub struct FutexWake<'a> {
+    futex: &'a AtomicU32,
+    count: u32,
+    mask: u32,
+    flags: u32,
+}
+
+impl<'a> FutexWake<'a> {
+    pub const fn new(futex: &'a AtomicU32, count: u32) -> Self {
+        Self {
+            futex,
+            count,
+            mask: FUTEX_BITSET_MATCH_ANY,
+            flags: FUTEX2_SIZE_U32 | FUTEX2_PRIVATE,
+        }
+    }
+
+    /// Only wake waiters with overlapping masks
+    pub const fn mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Allow the futex to live in memory shared with other processes
+    pub const fn shared(mut self) -> Self {
+        self.flags &= !FUTEX2_PRIVATE;
+        self
+    }
+}
+
+// SAFETY: futex bound to live long enough
+unsafe impl Operation for FutexWake<'_> {
+    type Output = usize;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::FutexWake::new(
+            self.futex.as_ptr(),
+            self.count.into(),
+            self.mask.into(),
+            self.flags,
+        )
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))
+    }
+}
+
+impl OneshotOperation for FutexWake<'_> {}
+
+impl MultishotOperation for FutexWake<'_> {}
+
+/// Wait on any of multiple futexes, producing the index of the one that got
+/// woken
+///
+/// Requires Linux 6.7 or newer
+#[must_use]
+pub struct FutexWaitV<'a> {
+    futexes: Vec<FutexVector>,
+    lifetime: PhantomData<&'a AtomicU32>,
+}
+
+impl<'a> FutexWaitV<'a> {
+    pub const fn new() -> Self {
+        Self {
+            futexes: Vec::new(),
+            lifetime: PhantomData,
+        }
+    }
+
+    /// Wait on a private futex as long as it holds the expected value
+    pub fn futex(self, futex: &'a AtomicU32, expected: u32) -> Self {
+        self.push(futex, expected, FUTEX2_SIZE_U32 | FUTEX2_PRIVATE)
+    }
+
+    /// Wait on a futex shared with other processes as long as it holds the
+    /// expected value
+    pub fn shared_futex(self, futex: &'a AtomicU32, expected: u32) -> Self {
+        self.push(futex, expected, FUTEX2_SIZE_U32)
+    }
+
+    fn push(mut self, futex: &'a AtomicU32, expected: u32, flags: u32) -> Self {
+        self.futexes.push(
+            FutexVector::new()
+                .uaddr(futex.as_ptr() as u64)
+                .val(expected.into())
+                .flags(flags),
+        );
+
+        self
+    }
+}
+
+impl Default for FutexWaitV<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SAFETY: futexes bound to live long enough and the vector is owned
+unsafe impl Operation for FutexWaitV<'_> {
+    type Output = usize;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::FutexWaitV::new(
+            self.futexes.as_ptr(),
+            u32::try_from(self.futexes.len()).unwrap(),
+        )
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(std::mem::take(&mut self.futexes))
+    }
+}
+
+impl OneshotOperation for FutexWaitV<'_> {}
+
+impl MultishotOperation for FutexWaitV<'_> {}
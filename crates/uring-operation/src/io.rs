@@ -1,13 +1,14 @@
 use std::{
     io::{Error, Result},
     mem::MaybeUninit,
-    os::fd::{AsRawFd, BorrowedFd},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
     pin::Pin,
 };
 
 use io_uring::{cqueue, opcode, squeue, types::Fd};
+use uring_reactor::keep_alive;
 
-use crate::operation::Operation;
+use crate::operation::{MultishotOperation, OneshotOperation, Operation};
 
 #[must_use]
 pub struct Read<'a> {
@@ -20,7 +21,7 @@ impl<'a> Read<'a> {
         Self { file, buffer }
     }
 
-    fn uninitialized_section_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+    const fn uninitialized_section_mut(&mut self) -> &mut [MaybeUninit<u8>] {
         // SAFETY: we're correctly getting a slice of the uninitialzed part
         unsafe {
             std::slice::from_raw_parts_mut(
@@ -32,7 +33,7 @@ impl<'a> Read<'a> {
 }
 
 // SAFETY: file bound to live long enough and buffer is owned
-unsafe impl<'a> Operation for Read<'a> {
+unsafe impl Operation for Read<'_> {
     type Output = Vec<u8>;
 
     fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
@@ -61,8 +62,16 @@ unsafe impl<'a> Operation for Read<'a> {
 
         Ok(std::mem::take(&mut self.buffer))
     }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(std::mem::take(&mut self.buffer))
+    }
 }
 
+impl OneshotOperation for Read<'_> {}
+
+impl MultishotOperation for Read<'_> {}
+
 #[must_use]
 pub struct Write<'a> {
     file: BorrowedFd<'a>,
@@ -76,7 +85,7 @@ impl<'a> Write<'a> {
 }
 
 // SAFETY: file and buffer bound to live long enough
-unsafe impl<'a> Operation for Write<'a> {
+unsafe impl Operation for Write<'_> {
     type Output = usize;
 
     fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
@@ -99,35 +108,140 @@ unsafe impl<'a> Operation for Write<'a> {
     }
 }
 
+impl OneshotOperation for Write<'_> {}
+
+impl MultishotOperation for Write<'_> {}
+
+bitflags::bitflags! {
+    /// Flags for moving data between pipes and other files
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct SpliceFlags: libc::c_uint {
+        const MOVE = libc::SPLICE_F_MOVE;
+        const NON_BLOCKING = libc::SPLICE_F_NONBLOCK;
+        const MORE = libc::SPLICE_F_MORE;
+    }
+}
+
+/// Move data between files where at least one of them is a pipe
 #[must_use]
 pub struct Splice<'a> {
     input: BorrowedFd<'a>,
+    input_offset: i64,
     output: BorrowedFd<'a>,
+    output_offset: i64,
     amount: u32,
+    flags: SpliceFlags,
 }
 
 impl<'a> Splice<'a> {
     pub const fn new(input: BorrowedFd<'a>, output: BorrowedFd<'a>, amount: u32) -> Self {
         Self {
             input,
+            input_offset: -1,
             output,
+            output_offset: -1,
             amount,
+            flags: SpliceFlags::empty(),
         }
     }
+
+    /// Read from the given position instead of the file offset, which isn't
+    /// allowed for pipes
+    ///
+    /// # Panics
+    ///
+    /// If the offset overflows the signed type used by `io_uring`
+    pub const fn input_offset(mut self, offset: u64) -> Self {
+        assert!(offset <= i64::MAX.cast_unsigned(), "offset out of range");
+        self.input_offset = offset.cast_signed();
+        self
+    }
+
+    /// Write to the given position instead of the file offset, which isn't
+    /// allowed for pipes
+    ///
+    /// # Panics
+    ///
+    /// If the offset overflows the signed type used by `io_uring`
+    pub const fn output_offset(mut self, offset: u64) -> Self {
+        assert!(offset <= i64::MAX.cast_unsigned(), "offset out of range");
+        self.output_offset = offset.cast_signed();
+        self
+    }
+
+    pub const fn flags(mut self, flags: SpliceFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
 }
 
 // SAFETY: files bound to live long enough
-unsafe impl<'a> Operation for Splice<'a> {
+unsafe impl Operation for Splice<'_> {
     type Output = usize;
 
     fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
         opcode::Splice::new(
             Fd(self.input.as_raw_fd()),
-            -1,
+            self.input_offset,
+            Fd(self.output.as_raw_fd()),
+            self.output_offset,
+            self.amount,
+        )
+        .flags(self.flags.bits())
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))
+    }
+}
+
+impl OneshotOperation for Splice<'_> {}
+
+impl MultishotOperation for Splice<'_> {}
+
+/// Duplicate data between pipes without consuming it from the input
+#[must_use]
+pub struct Tee<'a> {
+    input: BorrowedFd<'a>,
+    output: BorrowedFd<'a>,
+    amount: u32,
+    flags: SpliceFlags,
+}
+
+impl<'a> Tee<'a> {
+    pub const fn new(input: BorrowedFd<'a>, output: BorrowedFd<'a>, amount: u32) -> Self {
+        Self {
+            input,
+            output,
+            amount,
+            flags: SpliceFlags::empty(),
+        }
+    }
+
+    pub const fn flags(mut self, flags: SpliceFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+}
+
+// SAFETY: pipes bound to live long enough
+unsafe impl Operation for Tee<'_> {
+    type Output = usize;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::Tee::new(
+            Fd(self.input.as_raw_fd()),
             Fd(self.output.as_raw_fd()),
-            -1,
             self.amount,
         )
+        .flags(self.flags.bits())
         .build()
     }
 
@@ -141,3 +255,93 @@ unsafe impl<'a> Operation for Splice<'a> {
             .map_err(|_| Error::from_raw_os_error(-entry.result()))
     }
 }
+
+impl OneshotOperation for Tee<'_> {}
+
+impl MultishotOperation for Tee<'_> {}
+
+/// Both ends of a pipe, used as the intermediate for splicing data between
+/// files that aren't pipes themselves
+pub struct PipePair {
+    reader: OwnedFd,
+    writer: OwnedFd,
+}
+
+impl PipePair {
+    /// Create a pipe closed on exec
+    ///
+    /// # Errors
+    ///
+    /// If creating the pipe fails
+    pub fn new() -> Result<Self> {
+        let mut files = [0; 2];
+
+        // SAFETY: valid pointer to storage for both descriptors
+        if unsafe { libc::pipe2(files.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+            return Err(Error::last_os_error());
+        }
+
+        // SAFETY: the kernel should have provided us valid descriptors
+        unsafe {
+            Ok(Self {
+                reader: OwnedFd::from_raw_fd(files[0]),
+                writer: OwnedFd::from_raw_fd(files[1]),
+            })
+        }
+    }
+
+    /// Create a pipe and resize its buffer to hold at least the given amount
+    /// of bytes
+    ///
+    /// # Errors
+    ///
+    /// If creating or resizing the pipe fails
+    pub fn with_capacity(capacity: usize) -> Result<Self> {
+        let pipe = Self::new()?;
+        pipe.set_capacity(capacity)?;
+        Ok(pipe)
+    }
+
+    /// Resize the pipe's buffer, which the kernel rounds up to a power of two
+    /// amount of pages
+    ///
+    /// # Errors
+    ///
+    /// If the capacity exceeds the limit allowed for the user or is smaller
+    /// than the currently buffered data
+    pub fn set_capacity(&self, capacity: usize) -> Result<usize> {
+        let capacity =
+            libc::c_int::try_from(capacity).map_err(|_| Error::from_raw_os_error(libc::EINVAL))?;
+
+        // SAFETY: no pointers involved
+        let result = unsafe { libc::fcntl(self.writer.as_raw_fd(), libc::F_SETPIPE_SZ, capacity) };
+        usize::try_from(result).map_err(|_| Error::last_os_error())
+    }
+
+    /// Size of the pipe's buffer in bytes
+    ///
+    /// # Errors
+    ///
+    /// If querying the pipe fails
+    pub fn capacity(&self) -> Result<usize> {
+        // SAFETY: no pointers involved
+        let result = unsafe { libc::fcntl(self.writer.as_raw_fd(), libc::F_GETPIPE_SZ) };
+        usize::try_from(result).map_err(|_| Error::last_os_error())
+    }
+
+    #[must_use]
+    pub fn reader(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+
+    #[must_use]
+    pub fn writer(&self) -> BorrowedFd<'_> {
+        self.writer.as_fd()
+    }
+
+    /// Split into the reading and writing ends
+    #[must_use]
+    pub fn into_parts(self) -> (OwnedFd, OwnedFd) {
+        (self.reader, self.writer)
+    }
+}
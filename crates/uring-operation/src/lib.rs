@@ -1,11 +1,69 @@
+mod ancillary;
+mod buffer;
+mod combinator;
+mod command;
 mod common;
+mod error;
+mod fs;
+mod futex;
 mod io;
+mod macros;
 mod net;
 mod operation;
+mod poll;
+mod process;
+mod xattr;
 
 pub use crate::{
-    common::{Cancel, Close, Raw},
-    io::{Read, Splice, Write},
-    net::{Accept, Shutdown},
-    operation::{Multishot, Oneshot, Operation},
+    ancillary::{AncillaryBuilder, AncillaryMessage},
+    buffer::{BufferRing, ProvidedBuffer},
+    combinator::{race, AndThen, Map, OperationExt, Race, Raced, RetryOn},
+    command::{UringCmd, UringCmd80},
+    common::{Cancel, Close, Nop, Raw},
+    error::{OperationError, OperationErrorKind},
+    fs::{Fsync, Ftruncate, IoBuffer, OpenAt, ReadAt, Statx, WriteAt},
+    futex::{FutexWait, FutexWaitV, FutexWake},
+    io::{PipePair, Read, Splice, SpliceFlags, Tee, Write},
+    net::{
+        Accept,
+        AcceptMulti,
+        Bind,
+        Connect,
+        DirectAcceptMulti,
+        DirectSocket,
+        Listen,
+        MessageFlags,
+        ReceivedMessage,
+        Recv,
+        RecvFrom,
+        RecvMsg,
+        RecvMsgMulti,
+        Send,
+        SendMsg,
+        SendMsgZc,
+        SendTo,
+        SendZc,
+        Shutdown,
+        Socket,
+        ZeroCopy,
+    },
+    operation::{
+        Multishot,
+        MultishotOperation,
+        Oneshot,
+        OneshotOperation,
+        Operation,
+        RestartPolicy,
+    },
+    poll::{Poll, PollMulti, PollRemove, PollUpdate, Readiness},
+    process::{ChildStatus, WaitTarget, Waitid},
+    xattr::{FGetXattr, FSetXattr, GetXattr, SetXattr},
 };
+
+#[doc(hidden)]
+pub mod __private {
+    pub use io_uring;
+    pub use uring_reactor::keep_alive;
+
+    pub use crate::macros::Owned;
+}
@@ -0,0 +1,170 @@
+/// Declare an operation from its owned fields, how to build its submission and
+/// how to turn a successful result into its output
+///
+/// Fields must be owned or `'static` and are kept together in a pinned
+/// allocation, so that pointers into them stay valid while the kernel uses
+/// them. When the operation is dropped before its final completion, the
+/// allocation is handed over to the reactor until that completion arrived.
+/// The build block gets mutable references to the fields bound by name, while
+/// the completion block only gets shared references and runs after negative
+/// results were turned into errors.
+///
+/// # Safety
+///
+/// The build block has to be marked `unsafe`, as nothing checks what its
+/// submission points to, and is an unsafe context. Pointers handed to the
+/// kernel must point into the fields, or memory owned by them, which must stay
+/// at the same address until the final completion arrived. Owning a value
+/// doesn't make its address stable, such as the contents of a `Vec` that
+/// grows or an `Rc<RefCell<_>>` shared with code that replaces them.
+///
+/// ```
+/// use std::os::fd::{AsRawFd, OwnedFd};
+///
+/// use io_uring::{opcode, types::Fd};
+///
+/// uring_operation::operation! {
+///     /// Read into an owned buffer
+///     #[must_use]
+///     pub struct ReadOwned {
+///         file: OwnedFd,
+///         buffer: Box<[u8; 512]>,
+///     }
+///
+///     // SAFETY: the boxed buffer is never replaced
+///     unsafe build {
+///         opcode::Read::new(Fd(file.as_raw_fd()), buffer.as_mut_ptr(), 512).build()
+///     }
+///
+///     complete(result) -> Vec<u8> {
+///         buffer[..result as usize].to_vec()
+///     }
+/// }
+/// ```
+///
+/// Borrowed buffers can't be smuggled into the operation
+///
+/// ```compile_fail,E0477
+/// use io_uring::{opcode, types::Fd};
+///
+/// uring_operation::operation! {
+///     pub struct ReadBorrowed<'a> {
+///         buffer: &'a mut [u8],
+///     }
+///
+///     unsafe build {
+///         opcode::Read::new(Fd(0), buffer.as_mut_ptr(), 0).build()
+///     }
+///
+///     complete(result) -> u32 {
+///         result
+///     }
+/// }
+/// ```
+///
+/// Neither inside of other types
+///
+/// ```compile_fail,E0477
+/// use std::os::fd::{AsRawFd, BorrowedFd};
+///
+/// use io_uring::{opcode, types::Fd};
+///
+/// uring_operation::operation! {
+///     pub struct CloseBorrowed<'a> {
+///         file: BorrowedFd<'a>,
+///     }
+///
+///     unsafe build {
+///         opcode::Close::new(Fd(file.as_raw_fd())).build()
+///     }
+///
+///     complete(result) -> u32 {
+///         result
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! operation {
+    (
+        $(#[$attribute:meta])*
+        $visibility:vis struct $name:ident $(<$($lifetime:lifetime),+ $(,)?>)? {
+            $($(#[$field_attribute:meta])* $field:ident: $kind:ty),* $(,)?
+        }
+
+        unsafe build $build:block
+
+        complete($result:ident) -> $output:ty $complete:block
+    ) => {
+        $(#[$attribute])*
+        $visibility struct $name $(<$($lifetime),+>)? {
+            fields: ::core::option::Option<::core::pin::Pin<::std::boxed::Box<($($kind,)*)>>>,
+            _owned: ::core::marker::PhantomData<($($crate::__private::Owned<$kind>,)*)>,
+        }
+
+        impl $(<$($lifetime),+>)? $name $(<$($lifetime),+>)? {
+            #[allow(clippy::too_many_arguments)]
+            $visibility fn new($($field: $kind),*) -> Self {
+                Self {
+                    fields: ::core::option::Option::Some(::std::boxed::Box::pin(($($field,)*))),
+                    _owned: ::core::marker::PhantomData,
+                }
+            }
+        }
+
+        // SAFETY: all fields are owned or `'static` and pinned on the heap until
+        // the final completion, while the build block vouches for its pointers
+        unsafe impl $(<$($lifetime),+>)? $crate::Operation for $name $(<$($lifetime),+>)? {
+            type Output = $output;
+
+            #[allow(unused_unsafe)]
+            fn build_submission(
+                self: ::core::pin::Pin<&mut Self>,
+            ) -> $crate::__private::io_uring::squeue::Entry {
+                // SAFETY: the fields are only handed out by reference and stay
+                // pinned in their allocation
+                let fields = unsafe {
+                    let this = self.get_unchecked_mut();
+                    this.fields.as_mut().unwrap().as_mut().get_unchecked_mut()
+                };
+
+                #[allow(unused_variables)]
+                let ($($field,)*) = fields;
+
+                // SAFETY: vouched for by the caller
+                unsafe { $build }
+            }
+
+            unsafe fn process_completion(
+                self: ::core::pin::Pin<&mut Self>,
+                entry: $crate::__private::io_uring::cqueue::Entry,
+            ) -> ::std::io::Result<Self::Output> {
+                if entry.result().is_negative() {
+                    return Err(::std::io::Error::from_raw_os_error(-entry.result()));
+                }
+
+                #[allow(unused_variables)]
+                let ($($field,)*) = &**self.into_ref().get_ref().fields.as_ref().unwrap();
+                let $result = entry.result().cast_unsigned();
+
+                Ok($complete)
+            }
+
+            fn detach_resources(
+                self: ::core::pin::Pin<&mut Self>,
+            ) -> ::std::boxed::Box<dyn FnMut($crate::__private::io_uring::cqueue::Entry)> {
+                // SAFETY: the pinned allocation is moved as a whole
+                let fields = unsafe { self.get_unchecked_mut() }.fields.take();
+                $crate::__private::keep_alive(fields)
+            }
+        }
+
+        impl $(<$($lifetime),+>)? $crate::OneshotOperation for $name $(<$($lifetime),+>)? {}
+
+        impl $(<$($lifetime),+>)? $crate::MultishotOperation for $name $(<$($lifetime),+>)? {}
+    };
+}
+
+/// Marker that's only well-formed for owned or `'static` types, which makes
+/// declaring operations with borrowed fields fail
+#[doc(hidden)]
+pub struct Owned<T: ?Sized + 'static>(::core::marker::PhantomData<T>);
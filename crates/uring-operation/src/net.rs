@@ -1,33 +1,49 @@
 use std::{
-    io::{Error, Result},
+    io::{Error, ErrorKind, Result},
+    marker::PhantomData,
     mem::MaybeUninit,
     os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
     pin::Pin,
 };
 
-use io_uring::{cqueue, opcode, squeue, types::Fd};
-use socket2::SockAddr;
+use io_uring::{
+    cqueue,
+    opcode,
+    squeue,
+    types::{DestinationSlot, Fd, Fixed, RecvMsgOut},
+};
+use socket2::{Domain, Protocol, SockAddr, Type};
+use uring_reactor::keep_alive;
+
+use crate::{
+    ancillary::{AncillaryBuilder, AncillaryMessage},
+    buffer::BufferRing,
+    common::close_created,
+    operation::{MultishotOperation, OneshotOperation, Operation, RestartPolicy},
+};
 
-use crate::operation::Operation;
+/// Peer address written by the kernel, kept on the heap like [`ReceiveState`]
+struct AddressState {
+    storage: MaybeUninit<libc::sockaddr_storage>,
+    length: libc::socklen_t,
+}
 
 #[must_use]
 pub struct Accept<'a> {
     socket: BorrowedFd<'a>,
     flags: libc::c_int,
-    storage: MaybeUninit<libc::sockaddr_storage>,
-    length: libc::socklen_t,
+    address: Option<Box<AddressState>>,
 }
 
 impl<'a> Accept<'a> {
-    pub const fn new(socket: BorrowedFd<'a>) -> Self {
+    pub fn new(socket: BorrowedFd<'a>) -> Self {
         Self {
             socket,
             flags: 0,
-            storage: MaybeUninit::uninit(),
-            // there's no way the platform's address storage overflows the specific length type
-            // that's solely meant for representing it's length
-            #[allow(clippy::cast_possible_truncation)]
-            length: std::mem::size_of::<libc::sockaddr_storage>() as _,
+            address: Some(Box::new(AddressState {
+                storage: MaybeUninit::uninit(),
+                length: 0,
+            })),
         }
     }
 
@@ -40,19 +56,42 @@ impl<'a> Accept<'a> {
         self.flags |= libc::SOCK_CLOEXEC;
         self
     }
+
+    /// Accept connections continuously from a single submission, to be used
+    /// with [`Operation::submit_multishot`]
+    pub fn multishot(self) -> AcceptMulti<'a> {
+        AcceptMulti {
+            socket: self.socket,
+            flags: self.flags,
+        }
+    }
 }
 
-// SAFETY: socket bound to live long enough and the address data is owned
-unsafe impl<'a> Operation for Accept<'a> {
+// SAFETY: socket bound to live long enough and the address data is owned on
+// the heap
+unsafe impl Operation for Accept<'_> {
     type Output = (OwnedFd, SockAddr);
 
     fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        let socket = self.socket.as_raw_fd();
+        let flags = self.flags;
+        let address = self.address.as_mut().unwrap();
+
+        // the kernel overwrites the length with the one of the previous peer
+        //
+        // there's no way the platform's address storage overflows the specific
+        // length type that's solely meant for representing it's length
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            address.length = std::mem::size_of::<libc::sockaddr_storage>() as _;
+        }
+
         opcode::Accept::new(
-            Fd(self.socket.as_raw_fd()),
-            self.storage.as_mut_ptr().cast(),
-            &mut self.length,
+            Fd(socket),
+            address.storage.as_mut_ptr().cast(),
+            &raw mut address.length,
         )
-        .flags(self.flags)
+        .flags(flags)
         .build()
     }
 
@@ -64,16 +103,124 @@ unsafe impl<'a> Operation for Accept<'a> {
             return Err(Error::from_raw_os_error(-entry.result()));
         }
 
+        let address = self.address.as_ref().unwrap();
+
         // SAFETY: the kernel should have provided us valid values
         unsafe {
             Ok((
                 OwnedFd::from_raw_fd(entry.result()),
-                SockAddr::new(self.storage.assume_init(), self.length),
+                SockAddr::new(address.storage.assume_init(), address.length),
             ))
         }
     }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        let address = self.address.take();
+
+        Box::new(move |entry| {
+            let _ = &address;
+            close_created(&entry);
+        })
+    }
+}
+
+impl OneshotOperation for Accept<'_> {}
+
+impl MultishotOperation for Accept<'_> {}
+
+/// Multishot accept that fetches peer addresses with `getpeername(2)` as the
+/// kernel doesn't provide them per connection, leaving them out for peers that
+/// already disconnected instead of failing the accept
+#[must_use]
+pub struct AcceptMulti<'a> {
+    socket: BorrowedFd<'a>,
+    flags: libc::c_int,
+}
+
+impl<'a> AcceptMulti<'a> {
+    /// Install accepted sockets into the registered file table instead, at
+    /// slots allocated by the kernel
+    pub const fn direct_descriptors(self) -> DirectAcceptMulti<'a> {
+        DirectAcceptMulti {
+            socket: self.socket,
+            flags: self.flags,
+        }
+    }
+}
+
+// SAFETY: socket bound to live long enough
+unsafe impl Operation for AcceptMulti<'_> {
+    type Output = (OwnedFd, Option<SockAddr>);
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::AcceptMulti::new(Fd(self.socket.as_raw_fd()))
+            .flags(self.flags)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        // SAFETY: the kernel should have provided us a valid descriptor
+        let socket = unsafe { OwnedFd::from_raw_fd(entry.result()) };
+        let address = socket2::SockRef::from(&socket).peer_addr().ok();
+
+        Ok((socket, address))
+    }
+
+    /// Successful connections ending the stream mean the kernel merely
+    /// couldn't keep it going, such as on completion queue overflow
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::on_success()
+    }
+
+    fn detach_resources(self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        Box::new(|entry| close_created(&entry))
+    }
+}
+
+impl MultishotOperation for AcceptMulti<'_> {}
+
+#[must_use]
+pub struct DirectAcceptMulti<'a> {
+    socket: BorrowedFd<'a>,
+    flags: libc::c_int,
+}
+
+// SAFETY: socket bound to live long enough
+unsafe impl Operation for DirectAcceptMulti<'_> {
+    type Output = Fixed;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::AcceptMulti::new(Fd(self.socket.as_raw_fd()))
+            .allocate_file_index(true)
+            .flags(self.flags)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(Fixed(entry.result().unsigned_abs()))
+    }
+
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::on_success()
+    }
 }
 
+impl MultishotOperation for DirectAcceptMulti<'_> {}
+
 #[must_use]
 pub struct Shutdown<'a> {
     socket: BorrowedFd<'a>,
@@ -100,7 +247,7 @@ impl<'a> Shutdown<'a> {
 }
 
 // SAFETY: file and buffer bound to live long enough
-unsafe impl<'a> Operation for Shutdown<'a> {
+unsafe impl Operation for Shutdown<'_> {
     type Output = ();
 
     fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
@@ -118,3 +265,1255 @@ unsafe impl<'a> Operation for Shutdown<'a> {
         Ok(())
     }
 }
+
+impl OneshotOperation for Shutdown<'_> {}
+
+impl MultishotOperation for Shutdown<'_> {}
+
+#[must_use]
+pub struct Socket {
+    domain: libc::c_int,
+    kind: libc::c_int,
+    protocol: libc::c_int,
+}
+
+impl Socket {
+    pub fn new(domain: Domain, kind: Type, protocol: Option<Protocol>) -> Self {
+        Self {
+            domain: domain.into(),
+            kind: kind.into(),
+            protocol: protocol.map_or(0, Into::into),
+        }
+    }
+
+    pub const fn non_blocking_socket(mut self) -> Self {
+        self.kind |= libc::SOCK_NONBLOCK;
+        self
+    }
+
+    pub const fn close_socket_on_exec(mut self) -> Self {
+        self.kind |= libc::SOCK_CLOEXEC;
+        self
+    }
+
+    /// Install the socket into the registered file table instead, either at
+    /// the given slot or at one allocated by the kernel
+    ///
+    /// # Panics
+    ///
+    /// On submission if the requested slot is out of the range supported by
+    /// the kernel
+    pub const fn direct_descriptor(self, slot: Option<u32>) -> DirectSocket {
+        DirectSocket {
+            domain: self.domain,
+            kind: self.kind,
+            protocol: self.protocol,
+            slot,
+        }
+    }
+}
+
+// SAFETY: no parameters that could get invalidated
+unsafe impl Operation for Socket {
+    type Output = OwnedFd;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::Socket::new(self.domain, self.kind, self.protocol).build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        // SAFETY: the kernel should have provided us a valid descriptor
+        Ok(unsafe { OwnedFd::from_raw_fd(entry.result()) })
+    }
+
+    fn detach_resources(self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        Box::new(|entry| close_created(&entry))
+    }
+}
+
+impl OneshotOperation for Socket {}
+
+impl MultishotOperation for Socket {}
+
+#[must_use]
+pub struct DirectSocket {
+    domain: libc::c_int,
+    kind: libc::c_int,
+    protocol: libc::c_int,
+    slot: Option<u32>,
+}
+
+// SAFETY: no parameters that could get invalidated
+unsafe impl Operation for DirectSocket {
+    type Output = Fixed;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let slot = self.slot.map_or_else(DestinationSlot::auto_target, |slot| {
+            DestinationSlot::try_from_slot_target(slot).unwrap()
+        });
+
+        opcode::Socket::new(self.domain, self.kind, self.protocol)
+            .file_index(Some(slot))
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        // the kernel only reports the index back when it allocated it itself
+        Ok(Fixed(
+            self.slot.unwrap_or_else(|| entry.result().unsigned_abs()),
+        ))
+    }
+}
+
+impl OneshotOperation for DirectSocket {}
+
+impl MultishotOperation for DirectSocket {}
+
+#[must_use]
+pub struct Connect<'a> {
+    socket: BorrowedFd<'a>,
+    address: Option<Box<SockAddr>>,
+}
+
+impl<'a> Connect<'a> {
+    pub fn new(socket: BorrowedFd<'a>, address: SockAddr) -> Self {
+        Self {
+            socket,
+            address: Some(Box::new(address)),
+        }
+    }
+}
+
+// SAFETY: socket bound to live long enough and the address is owned on the
+// heap
+unsafe impl Operation for Connect<'_> {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let address = self.address.as_ref().unwrap();
+
+        opcode::Connect::new(
+            Fd(self.socket.as_raw_fd()),
+            address.as_ptr().cast(),
+            address.len(),
+        )
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.address.take())
+    }
+}
+
+impl OneshotOperation for Connect<'_> {}
+
+impl MultishotOperation for Connect<'_> {}
+
+/// Requires Linux 6.11 or newer
+#[must_use]
+pub struct Bind<'a> {
+    socket: BorrowedFd<'a>,
+    address: Option<Box<SockAddr>>,
+}
+
+impl<'a> Bind<'a> {
+    pub fn new(socket: BorrowedFd<'a>, address: SockAddr) -> Self {
+        Self {
+            socket,
+            address: Some(Box::new(address)),
+        }
+    }
+}
+
+// SAFETY: socket bound to live long enough and the address is owned on the
+// heap
+unsafe impl Operation for Bind<'_> {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let address = self.address.as_ref().unwrap();
+
+        opcode::Bind::new(
+            Fd(self.socket.as_raw_fd()),
+            address.as_ptr().cast(),
+            address.len(),
+        )
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.address.take())
+    }
+}
+
+impl OneshotOperation for Bind<'_> {}
+
+impl MultishotOperation for Bind<'_> {}
+
+/// Requires Linux 6.11 or newer
+#[must_use]
+pub struct Listen<'a> {
+    socket: BorrowedFd<'a>,
+    backlog: libc::c_int,
+}
+
+impl<'a> Listen<'a> {
+    pub const fn new(socket: BorrowedFd<'a>, backlog: libc::c_int) -> Self {
+        Self { socket, backlog }
+    }
+}
+
+// SAFETY: socket bound to live long enough
+unsafe impl Operation for Listen<'_> {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::Listen::new(Fd(self.socket.as_raw_fd()), self.backlog).build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+}
+
+impl OneshotOperation for Listen<'_> {}
+
+impl MultishotOperation for Listen<'_> {}
+
+/// Kernel flag for skipping the initial optimistic attempt of a send or
+/// receive operation and arming poll straight away
+const IORING_RECVSEND_POLL_FIRST: u16 = 1 << 0;
+
+/// Kernel flag for having zero-copy notifications report whether the data
+/// ended up being copied
+const IORING_SEND_ZC_REPORT_USAGE: u16 = 1 << 3;
+
+/// Kernel flag set in the result of a zero-copy notification when the data
+/// ended up being copied anyway
+const IORING_NOTIF_USAGE_ZC_COPIED: u32 = 1 << 31;
+
+bitflags::bitflags! {
+    /// Flags for socket send and receive operations
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct MessageFlags: libc::c_int {
+        const WAIT_ALL = libc::MSG_WAITALL;
+        const PEEK = libc::MSG_PEEK;
+        const NO_SIGNAL = libc::MSG_NOSIGNAL;
+        const DONT_WAIT = libc::MSG_DONTWAIT;
+        const MORE = libc::MSG_MORE;
+        const CMSG_CLOSE_ON_EXEC = libc::MSG_CMSG_CLOEXEC;
+        const TRUNCATED = libc::MSG_TRUNC;
+        const CONTROL_TRUNCATED = libc::MSG_CTRUNC;
+    }
+}
+
+#[must_use]
+pub struct Send<'a> {
+    socket: BorrowedFd<'a>,
+    buffer: &'a [u8],
+    flags: MessageFlags,
+    priority: u16,
+}
+
+impl<'a> Send<'a> {
+    pub const fn new(socket: BorrowedFd<'a>, buffer: &'a [u8]) -> Self {
+        Self {
+            socket,
+            buffer,
+            flags: MessageFlags::empty(),
+            priority: 0,
+        }
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Arm poll before attempting the operation, useful when the socket is
+    /// expected to not be ready yet
+    pub const fn poll_first(mut self) -> Self {
+        self.priority |= IORING_RECVSEND_POLL_FIRST;
+        self
+    }
+}
+
+// SAFETY: socket and buffer bound to live long enough
+unsafe impl Operation for Send<'_> {
+    type Output = usize;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::Send::new(
+            Fd(self.socket.as_raw_fd()),
+            self.buffer.as_ptr(),
+            u32::try_from(self.buffer.len()).unwrap(),
+        )
+        .flags(self.flags.bits())
+        .ioprio(self.priority)
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))
+    }
+}
+
+impl OneshotOperation for Send<'_> {}
+
+impl MultishotOperation for Send<'_> {}
+
+#[must_use]
+pub struct Recv<'a> {
+    socket: BorrowedFd<'a>,
+    buffer: Vec<u8>,
+    flags: MessageFlags,
+    priority: u16,
+}
+
+impl<'a> Recv<'a> {
+    pub const fn new(socket: BorrowedFd<'a>, buffer: Vec<u8>) -> Self {
+        Self {
+            socket,
+            buffer,
+            flags: MessageFlags::empty(),
+            priority: 0,
+        }
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Arm poll before attempting the operation, useful when the socket is
+    /// expected to not be ready yet
+    pub const fn poll_first(mut self) -> Self {
+        self.priority |= IORING_RECVSEND_POLL_FIRST;
+        self
+    }
+}
+
+// SAFETY: socket bound to live long enough and buffer is owned
+unsafe impl Operation for Recv<'_> {
+    type Output = Vec<u8>;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let this = self.get_mut();
+        let spare = this.buffer.spare_capacity_mut();
+
+        opcode::Recv::new(
+            Fd(this.socket.as_raw_fd()),
+            spare.as_mut_ptr().cast(),
+            u32::try_from(spare.len()).unwrap(),
+        )
+        .flags(this.flags.bits())
+        .ioprio(this.priority)
+        .build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let amount: usize = entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))?;
+
+        // SAFETY: we trust the kernel to tell us how much was read into the buffer
+        unsafe {
+            let new = self.buffer.len() + amount;
+            self.buffer.set_len(new);
+        }
+
+        Ok(std::mem::take(&mut self.buffer))
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(std::mem::take(&mut self.buffer))
+    }
+}
+
+impl OneshotOperation for Recv<'_> {}
+
+impl MultishotOperation for Recv<'_> {}
+
+#[must_use]
+pub struct SendTo<'a> {
+    socket: BorrowedFd<'a>,
+    buffer: &'a [u8],
+    address: Option<Box<SockAddr>>,
+    flags: MessageFlags,
+    priority: u16,
+}
+
+impl<'a> SendTo<'a> {
+    pub fn new(socket: BorrowedFd<'a>, buffer: &'a [u8], address: SockAddr) -> Self {
+        Self {
+            socket,
+            buffer,
+            address: Some(Box::new(address)),
+            flags: MessageFlags::empty(),
+            priority: 0,
+        }
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Arm poll before attempting the operation, useful when the socket is
+    /// expected to not be ready yet
+    pub const fn poll_first(mut self) -> Self {
+        self.priority |= IORING_RECVSEND_POLL_FIRST;
+        self
+    }
+}
+
+// SAFETY: socket and buffer bound to live long enough and the address is owned
+// on the heap
+unsafe impl Operation for SendTo<'_> {
+    type Output = usize;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let address = self.address.as_ref().unwrap();
+
+        opcode::Send::new(
+            Fd(self.socket.as_raw_fd()),
+            self.buffer.as_ptr(),
+            u32::try_from(self.buffer.len()).unwrap(),
+        )
+        .dest_addr(address.as_ptr().cast())
+        .dest_addr_len(address.len())
+        .flags(self.flags.bits())
+        .ioprio(self.priority)
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.address.take())
+    }
+}
+
+impl OneshotOperation for SendTo<'_> {}
+
+impl MultishotOperation for SendTo<'_> {}
+
+/// Message header for receiving into owned memory, kept on the heap along
+/// with everything it points to so that it can be handed over to the reactor
+/// when the operation is dropped before completing
+struct ReceiveState {
+    buffer: Vec<u8>,
+    control: Vec<u8>,
+    storage: MaybeUninit<libc::sockaddr_storage>,
+    vector: MaybeUninit<libc::iovec>,
+    header: MaybeUninit<libc::msghdr>,
+}
+
+impl ReceiveState {
+    fn new(buffer: Vec<u8>, control: Vec<u8>) -> Box<Self> {
+        Box::new(Self {
+            buffer,
+            control,
+            storage: MaybeUninit::uninit(),
+            vector: MaybeUninit::uninit(),
+            header: MaybeUninit::uninit(),
+        })
+    }
+
+    /// Point the message header at the spare capacity of the buffer, the
+    /// address storage and the control buffer
+    fn prepare(&mut self) -> *mut libc::msghdr {
+        let spare = self.buffer.spare_capacity_mut();
+
+        let vector = self.vector.write(libc::iovec {
+            iov_base: spare.as_mut_ptr().cast(),
+            iov_len: spare.len(),
+        });
+
+        // SAFETY: all zeroes is a valid message header
+        let header = self.header.write(unsafe { std::mem::zeroed() });
+        header.msg_name = self.storage.as_mut_ptr().cast();
+        // there's no way the platform's address storage overflows the specific length
+        // type that's solely meant for representing it's length
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            header.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as _;
+        }
+        header.msg_iov = vector;
+        header.msg_iovlen = 1;
+
+        if !self.control.is_empty() {
+            header.msg_control = self.control.as_mut_ptr().cast();
+            header.msg_controllen = self.control.len();
+        }
+
+        header
+    }
+
+    /// Take the received data
+    ///
+    /// # Safety
+    ///
+    /// The kernel must have received the amount into the prepared buffer
+    unsafe fn take_data(&mut self, amount: usize) -> Vec<u8> {
+        // SAFETY: the caller guarantees the amount
+        unsafe {
+            let new = self.buffer.len() + amount;
+            self.buffer.set_len(new);
+        }
+
+        std::mem::take(&mut self.buffer)
+    }
+
+    /// Message header as filled in by the kernel
+    ///
+    /// # Safety
+    ///
+    /// The kernel must have completed a receive with the prepared header
+    const unsafe fn header(&self) -> &libc::msghdr {
+        // SAFETY: the caller guarantees the header to be filled in
+        unsafe { self.header.assume_init_ref() }
+    }
+
+    /// Copy the peer address out of the storage, if there is one
+    ///
+    /// # Safety
+    ///
+    /// See [`ReceiveState::header`]
+    unsafe fn address(&self) -> Option<SockAddr> {
+        // SAFETY: the kernel wrote the address up to the length
+        let name = unsafe {
+            std::slice::from_raw_parts(
+                self.storage.as_ptr().cast(),
+                usize::try_from(self.header().msg_namelen)
+                    .unwrap()
+                    .min(std::mem::size_of::<libc::sockaddr_storage>()),
+            )
+        };
+
+        address_from_bytes(name)
+    }
+
+    /// Decode the received control messages
+    ///
+    /// # Safety
+    ///
+    /// See [`ReceiveState::header`]
+    unsafe fn ancillary(&self) -> Vec<AncillaryMessage> {
+        // SAFETY: the kernel filled in the control buffer up to the length
+        unsafe {
+            let length = self.header().msg_controllen.min(self.control.len());
+            AncillaryMessage::parse(&self.control[..length])
+        }
+    }
+}
+
+#[must_use]
+pub struct RecvFrom<'a> {
+    socket: BorrowedFd<'a>,
+    state: Option<Box<ReceiveState>>,
+    flags: MessageFlags,
+    priority: u16,
+}
+
+impl<'a> RecvFrom<'a> {
+    pub fn new(socket: BorrowedFd<'a>, buffer: Vec<u8>) -> Self {
+        Self {
+            socket,
+            state: Some(ReceiveState::new(buffer, Vec::new())),
+            flags: MessageFlags::empty(),
+            priority: 0,
+        }
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Arm poll before attempting the operation, useful when the socket is
+    /// expected to not be ready yet
+    pub const fn poll_first(mut self) -> Self {
+        self.priority |= IORING_RECVSEND_POLL_FIRST;
+        self
+    }
+}
+
+// SAFETY: socket bound to live long enough and the buffer and message header
+// data are owned on the heap
+unsafe impl Operation for RecvFrom<'_> {
+    type Output = (Vec<u8>, SockAddr);
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        let header = self.state.as_mut().unwrap().prepare();
+
+        opcode::RecvMsg::new(Fd(self.socket.as_raw_fd()), header)
+            .flags(self.flags.bits().cast_unsigned())
+            .ioprio(self.priority)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let amount: usize = entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))?;
+
+        let state = self.state.as_mut().unwrap();
+
+        // SAFETY: we trust the kernel to tell us how much was read into the buffer
+        // and to have filled in the peer address
+        unsafe {
+            let length = state.header().msg_namelen;
+            let address = SockAddr::new(state.storage.assume_init(), length);
+
+            Ok((state.take_data(amount), address))
+        }
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.state.take())
+    }
+}
+
+impl OneshotOperation for RecvFrom<'_> {}
+
+impl MultishotOperation for RecvFrom<'_> {}
+
+/// Data received by [`RecvMsg`] or [`RecvMsgMulti`]
+pub struct ReceivedMessage {
+    pub data: Vec<u8>,
+    pub address: Option<SockAddr>,
+    pub ancillary: Vec<AncillaryMessage>,
+    pub flags: MessageFlags,
+}
+
+/// Copy a raw socket address as written by the kernel, if there is one
+fn address_from_bytes(name: &[u8]) -> Option<SockAddr> {
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut storage = MaybeUninit::<libc::sockaddr_storage>::zeroed();
+    let length = name
+        .len()
+        .min(std::mem::size_of::<libc::sockaddr_storage>());
+
+    // SAFETY: the length is bounded by the storage and any prefix of zeroed
+    // storage is a valid address
+    unsafe {
+        std::ptr::copy_nonoverlapping(name.as_ptr(), storage.as_mut_ptr().cast(), length);
+        Some(SockAddr::new(
+            storage.assume_init(),
+            length.try_into().unwrap(),
+        ))
+    }
+}
+
+/// Message header for sending owned memory, kept on the heap along with
+/// everything it points to like [`ReceiveState`]
+struct SendState {
+    buffers: Vec<Vec<u8>>,
+    vectors: Vec<libc::iovec>,
+    address: Option<SockAddr>,
+    control: Vec<u8>,
+    header: MaybeUninit<libc::msghdr>,
+}
+
+impl SendState {
+    fn new(buffers: Vec<Vec<u8>>) -> Box<Self> {
+        Box::new(Self {
+            buffers,
+            vectors: Vec::new(),
+            address: None,
+            control: Vec::new(),
+            header: MaybeUninit::uninit(),
+        })
+    }
+
+    /// Point the message header at the buffers, the address and the control
+    /// data
+    fn prepare(&mut self) -> *const libc::msghdr {
+        self.vectors = self
+            .buffers
+            .iter()
+            .map(|buffer| libc::iovec {
+                iov_base: buffer.as_ptr().cast_mut().cast(),
+                iov_len: buffer.len(),
+            })
+            .collect();
+
+        // SAFETY: all zeroes is a valid message header
+        let header = self.header.write(unsafe { std::mem::zeroed() });
+        header.msg_iov = self.vectors.as_mut_ptr();
+        header.msg_iovlen = self.vectors.len();
+
+        if let Some(address) = &self.address {
+            header.msg_name = address.as_ptr().cast_mut().cast();
+            header.msg_namelen = address.len();
+        }
+
+        if !self.control.is_empty() {
+            header.msg_control = self.control.as_mut_ptr().cast();
+            header.msg_controllen = self.control.len();
+        }
+
+        header
+    }
+}
+
+#[must_use]
+pub struct SendMsg<'a> {
+    socket: BorrowedFd<'a>,
+    state: Option<Box<SendState>>,
+    files: PhantomData<AncillaryBuilder<'a>>,
+    flags: MessageFlags,
+    priority: u16,
+}
+
+impl<'a> SendMsg<'a> {
+    pub fn new(socket: BorrowedFd<'a>, buffers: Vec<Vec<u8>>) -> Self {
+        Self {
+            socket,
+            state: Some(SendState::new(buffers)),
+            files: PhantomData,
+            flags: MessageFlags::empty(),
+            priority: 0,
+        }
+    }
+
+    /// Send to the given address instead of the connected peer
+    pub fn address(mut self, address: SockAddr) -> Self {
+        if let Some(state) = &mut self.state {
+            state.address = Some(address);
+        }
+
+        self
+    }
+
+    pub fn ancillary(mut self, ancillary: AncillaryBuilder<'a>) -> Self {
+        if let Some(state) = &mut self.state {
+            state.control = ancillary.into_bytes();
+        }
+
+        self
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Arm poll before attempting the operation, useful when the socket is
+    /// expected to not be ready yet
+    pub const fn poll_first(mut self) -> Self {
+        self.priority |= IORING_RECVSEND_POLL_FIRST;
+        self
+    }
+}
+
+// SAFETY: socket and passed files bound to live long enough and the buffers,
+// address, control data and message header are owned on the heap
+unsafe impl Operation for SendMsg<'_> {
+    type Output = usize;
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        let header = self.state.as_mut().unwrap().prepare();
+
+        opcode::SendMsg::new(Fd(self.socket.as_raw_fd()), header)
+            .flags(self.flags.bits().cast_unsigned())
+            .ioprio(self.priority)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.state.take())
+    }
+}
+
+impl OneshotOperation for SendMsg<'_> {}
+
+impl MultishotOperation for SendMsg<'_> {}
+
+#[must_use]
+pub struct RecvMsg<'a> {
+    socket: BorrowedFd<'a>,
+    state: Option<Box<ReceiveState>>,
+    flags: MessageFlags,
+    priority: u16,
+}
+
+impl<'a> RecvMsg<'a> {
+    pub fn new(socket: BorrowedFd<'a>, buffer: Vec<u8>) -> Self {
+        Self {
+            socket,
+            state: Some(ReceiveState::new(buffer, Vec::new())),
+            flags: MessageFlags::empty(),
+            priority: 0,
+        }
+    }
+
+    /// Reserve space for receiving control messages, as computed by
+    /// `CMSG_SPACE` for each expected message
+    pub fn ancillary_capacity(mut self, capacity: usize) -> Self {
+        if let Some(state) = &mut self.state {
+            state.control = vec![0; capacity];
+        }
+
+        self
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Arm poll before attempting the operation, useful when the socket is
+    /// expected to not be ready yet
+    pub const fn poll_first(mut self) -> Self {
+        self.priority |= IORING_RECVSEND_POLL_FIRST;
+        self
+    }
+}
+
+// SAFETY: socket bound to live long enough and the buffers and message header
+// data are owned on the heap
+unsafe impl Operation for RecvMsg<'_> {
+    type Output = ReceivedMessage;
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        let header = self.state.as_mut().unwrap().prepare();
+
+        opcode::RecvMsg::new(Fd(self.socket.as_raw_fd()), header)
+            .flags(self.flags.bits().cast_unsigned())
+            .ioprio(self.priority)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let amount: usize = entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))?;
+
+        let state = self.state.as_mut().unwrap();
+
+        // SAFETY: we trust the kernel to tell us how much was read into the buffers
+        // and to have filled in the message header
+        unsafe {
+            Ok(ReceivedMessage {
+                address: state.address(),
+                ancillary: state.ancillary(),
+                flags: MessageFlags::from_bits_retain(state.header().msg_flags),
+                data: state.take_data(amount),
+            })
+        }
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        let state = self.state.take();
+
+        // files passed along with a message that nobody receives anymore are
+        // closed instead of leaking
+        Box::new(move |entry| {
+            if let Some(state) = state.as_ref().filter(|_| entry.result() >= 0) {
+                // SAFETY: the kernel completed the receive with the header
+                drop(unsafe { state.ancillary() });
+            }
+        })
+    }
+}
+
+impl OneshotOperation for RecvMsg<'_> {}
+
+impl MultishotOperation for RecvMsg<'_> {}
+
+/// Multishot receive selecting buffers from a [`BufferRing`], which need to
+/// fit the message header, address, control data and payload
+#[must_use]
+pub struct RecvMsgMulti<'a> {
+    socket: BorrowedFd<'a>,
+    buffers: &'a BufferRing<'a>,
+    flags: MessageFlags,
+    header: Option<Box<libc::msghdr>>,
+}
+
+impl<'a> RecvMsgMulti<'a> {
+    pub fn new(socket: BorrowedFd<'a>, buffers: &'a BufferRing<'a>) -> Self {
+        // SAFETY: all zeroes is a valid message header
+        let mut header: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+        // there's no way the platform's address storage overflows the specific length
+        // type that's solely meant for representing it's length
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            header.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as _;
+        }
+
+        Self {
+            socket,
+            buffers,
+            flags: MessageFlags::empty(),
+            header: Some(header),
+        }
+    }
+
+    /// Reserve space for receiving control messages, as computed by
+    /// `CMSG_SPACE` for each expected message
+    pub fn ancillary_capacity(mut self, capacity: usize) -> Self {
+        if let Some(header) = &mut self.header {
+            header.msg_controllen = capacity;
+        }
+
+        self
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+}
+
+// SAFETY: socket and buffers bound to live long enough and the message header
+// is owned on the heap
+unsafe impl Operation for RecvMsgMulti<'_> {
+    type Output = ReceivedMessage;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let header: &libc::msghdr = self.header.as_ref().unwrap();
+
+        opcode::RecvMsgMulti::new(Fd(self.socket.as_raw_fd()), header, self.buffers.group())
+            .flags(self.flags.bits().cast_unsigned())
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let amount: usize = entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))?;
+
+        let index = cqueue::buffer_select(entry.flags())
+            .ok_or_else(|| Error::from_raw_os_error(libc::ENOBUFS))?;
+
+        // SAFETY: the kernel selected the buffer from our ring
+        let buffer = unsafe { self.buffers.take(index, amount) };
+        let message = RecvMsgOut::parse(&buffer, self.header.as_ref().unwrap())
+            .map_err(|()| Error::from_raw_os_error(libc::EINVAL))?;
+
+        Ok(ReceivedMessage {
+            data: message.payload_data().to_vec(),
+            address: address_from_bytes(message.name_data()),
+            // SAFETY: the control data was written by the kernel
+            ancillary: unsafe { AncillaryMessage::parse(message.control_data()) },
+            flags: MessageFlags::from_bits_retain(message.flags().cast_signed()),
+        })
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.header.take())
+    }
+}
+
+impl MultishotOperation for RecvMsgMulti<'_> {}
+
+/// Completion of a zero-copy send, yielded by the stream from
+/// [`Operation::submit_multishot`]
+///
+/// The kernel reports the amount of bytes sent first and only notifies once
+/// it stops referencing the buffers, after which the stream ends. Both
+/// completions carry the same user data, and the first one is flagged with
+/// `IORING_CQE_F_MORE`, so the reactor keeps the operation's slot and the
+/// buffers alive until the notification with `IORING_CQE_F_NOTIF` arrives.
+/// Zero-copy sends are never restarted once their buffers were released,
+/// whatever the restart policy.
+pub enum ZeroCopy<B> {
+    Sent(usize),
+    Released { buffers: B, copied: bool },
+}
+
+/// Turn a zero-copy completion into either the sent amount or the released
+/// buffers
+fn process_zero_copy<B>(entry: &cqueue::Entry, buffers: &mut Option<B>) -> Result<ZeroCopy<B>> {
+    let released = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "zero-copy buffers were already released",
+        )
+    };
+
+    if cqueue::notif(entry.flags()) {
+        return Ok(ZeroCopy::Released {
+            buffers: buffers.take().ok_or_else(released)?,
+            copied: entry.result().cast_unsigned() & IORING_NOTIF_USAGE_ZC_COPIED != 0,
+        });
+    }
+
+    if buffers.is_none() {
+        return Err(released());
+    }
+
+    entry
+        .result()
+        .try_into()
+        .map(ZeroCopy::Sent)
+        .map_err(|_| Error::from_raw_os_error(-entry.result()))
+}
+
+#[must_use]
+pub struct SendZc<'a> {
+    socket: BorrowedFd<'a>,
+    buffer: Option<Vec<u8>>,
+    address: Option<Box<SockAddr>>,
+    flags: MessageFlags,
+    priority: u16,
+}
+
+impl<'a> SendZc<'a> {
+    pub const fn new(socket: BorrowedFd<'a>, buffer: Vec<u8>) -> Self {
+        Self {
+            socket,
+            buffer: Some(buffer),
+            address: None,
+            flags: MessageFlags::empty(),
+            priority: 0,
+        }
+    }
+
+    /// Send to the given address instead of the connected peer
+    pub fn address(mut self, address: SockAddr) -> Self {
+        self.address = Some(Box::new(address));
+        self
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Arm poll before attempting the operation, useful when the socket is
+    /// expected to not be ready yet
+    pub const fn poll_first(mut self) -> Self {
+        self.priority |= IORING_RECVSEND_POLL_FIRST;
+        self
+    }
+}
+
+// SAFETY: socket bound to live long enough and the buffer and address are
+// owned on the heap until the kernel releases them
+unsafe impl Operation for SendZc<'_> {
+    type Output = ZeroCopy<Vec<u8>>;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        // completes with an error without touching the socket
+        let Some(buffer) = self.buffer.as_deref() else {
+            return opcode::Nop::new().build();
+        };
+
+        let mut entry = opcode::SendZc::new(
+            Fd(self.socket.as_raw_fd()),
+            buffer.as_ptr(),
+            u32::try_from(buffer.len()).unwrap(),
+        )
+        .flags(self.flags.bits())
+        .zc_flags(self.priority | IORING_SEND_ZC_REPORT_USAGE);
+
+        if let Some(address) = &self.address {
+            entry = entry
+                .dest_addr(address.as_ptr().cast())
+                .dest_addr_len(address.len());
+        }
+
+        entry.build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        process_zero_copy(&entry, &mut self.buffer)
+    }
+
+    fn restartable(&self) -> bool {
+        self.buffer.is_some()
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive((self.buffer.take(), self.address.take()))
+    }
+}
+
+impl MultishotOperation for SendZc<'_> {}
+
+#[must_use]
+pub struct SendMsgZc<'a> {
+    socket: BorrowedFd<'a>,
+    state: Option<Box<SendState>>,
+    files: PhantomData<AncillaryBuilder<'a>>,
+    flags: MessageFlags,
+    priority: u16,
+}
+
+impl<'a> SendMsgZc<'a> {
+    pub fn new(socket: BorrowedFd<'a>, buffers: Vec<Vec<u8>>) -> Self {
+        Self {
+            socket,
+            state: Some(SendState::new(buffers)),
+            files: PhantomData,
+            flags: MessageFlags::empty(),
+            priority: 0,
+        }
+    }
+
+    /// Send to the given address instead of the connected peer
+    pub fn address(mut self, address: SockAddr) -> Self {
+        if let Some(state) = &mut self.state {
+            state.address = Some(address);
+        }
+
+        self
+    }
+
+    pub fn ancillary(mut self, ancillary: AncillaryBuilder<'a>) -> Self {
+        if let Some(state) = &mut self.state {
+            state.control = ancillary.into_bytes();
+        }
+
+        self
+    }
+
+    pub const fn flags(mut self, flags: MessageFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Arm poll before attempting the operation, useful when the socket is
+    /// expected to not be ready yet
+    pub const fn poll_first(mut self) -> Self {
+        self.priority |= IORING_RECVSEND_POLL_FIRST;
+        self
+    }
+}
+
+// SAFETY: socket and passed files bound to live long enough and the buffers,
+// vectors, address, control data and message header are owned on the heap
+// until the kernel releases them
+unsafe impl Operation for SendMsgZc<'_> {
+    type Output = ZeroCopy<Vec<Vec<u8>>>;
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        let this = &mut *self;
+
+        // completes with an error without touching the socket
+        let Some(state) = this.state.as_mut() else {
+            return opcode::Nop::new().build();
+        };
+
+        opcode::SendMsgZc::new(Fd(this.socket.as_raw_fd()), state.prepare())
+            .flags(this.flags.bits().cast_unsigned())
+            .ioprio(this.priority | IORING_SEND_ZC_REPORT_USAGE)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        Ok(match process_zero_copy(&entry, &mut self.state)? {
+            ZeroCopy::Sent(amount) => ZeroCopy::Sent(amount),
+            ZeroCopy::Released { buffers, copied } => ZeroCopy::Released {
+                buffers: buffers.buffers,
+                copied,
+            },
+        })
+    }
+
+    fn restartable(&self) -> bool {
+        self.state.is_some()
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.state.take())
+    }
+}
+
+impl MultishotOperation for SendMsgZc<'_> {}
@@ -3,11 +3,14 @@ use std::{
     io::Result,
     pin::Pin,
     task::{ready, Context, Poll},
+    time::Duration,
 };
 
 use futures_core::Stream;
-use io_uring::{cqueue, squeue};
-use uring_reactor::{OperationId, Reactor};
+use io_uring::{cqueue, opcode, squeue, types::Timespec};
+use uring_reactor::{keep_alive, more, OperationId, Reactor};
+
+use crate::error::{OperationError, Origin};
 
 /// An abstract `io_uring` operation that can be submitted and completed
 ///
@@ -15,12 +18,16 @@ use uring_reactor::{OperationId, Reactor};
 ///
 /// The implementer must ensure that data used as operation parameters stays
 /// valid for the duration of the operation
-pub unsafe trait Operation: Sized {
+pub unsafe trait Operation<S = squeue::Entry, C = cqueue::Entry>: Sized
+where
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+{
     /// What this operation produces
     type Output;
 
     // Build an queue entry for submitting this operation
-    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry;
+    fn build_submission(self: Pin<&mut Self>) -> S;
 
     /// Process a queue entry repersenting the operation's completion
     ///
@@ -32,38 +39,184 @@ pub unsafe trait Operation: Sized {
     /// # Errors
     ///
     /// If the operation produced an error
-    unsafe fn process_completion(
-        self: Pin<&mut Self>,
-        entry: cqueue::Entry,
-    ) -> Result<Self::Output>;
+    unsafe fn process_completion(self: Pin<&mut Self>, entry: C) -> Result<Self::Output>;
+
+    /// Whether a oneshot operation should be submitted again after completing
+    /// with the given entry, giving it the chance to adjust its parameters
+    #[must_use]
+    fn retry_after(self: Pin<&mut Self>, _entry: &C) -> bool {
+        false
+    }
+
+    /// Policy for submitting the operation again when the kernel ends its
+    /// multishot stream, unless replaced through [`Multishot::restart_policy`]
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::never()
+    }
+
+    /// Whether the operation can be submitted again after its last
+    /// completion, which overrides any restart policy for operations that
+    /// handed their resources back
+    fn restartable(&self) -> bool {
+        true
+    }
+
+    /// Hand over what the kernel may still use once the future waiting for
+    /// the operation is dropped before it completed, as a handler that the
+    /// reactor keeps alive until the final completion and passes the
+    /// remaining completions to, so that it can release what they carry
+    ///
+    /// Operations pointing the kernel into memory they own must move it into
+    /// the handler, such as through [`uring_reactor::keep_alive`], as the
+    /// operation itself is freed right away
+    #[must_use]
+    fn detach_resources(self: Pin<&mut Self>) -> Box<dyn FnMut(C)> {
+        Box::new(|_| {})
+    }
 
     /// Create oneshot completion future
-    fn submit_oneshot(self, reactor: &Reactor) -> Oneshot<'_, Self> {
+    fn submit_oneshot(self, reactor: &Reactor<S, C>) -> Oneshot<'_, Self, S, C>
+    where
+        Self: OneshotOperation,
+    {
         Oneshot::new(reactor, self)
     }
 
     /// Create multishot completion stream
-    fn submit_multishot(self, reactor: &Reactor) -> Multishot<'_, Self> {
+    fn submit_multishot(self, reactor: &Reactor<S, C>) -> Multishot<'_, Self, S, C>
+    where
+        Self: MultishotOperation,
+    {
         Multishot::new(reactor, self)
     }
 }
 
+/// Operations that complete with a single entry, which can be awaited through
+/// [`Operation::submit_oneshot`]
+///
+/// Operations the kernel keeps posting completions for with `IORING_CQE_F_MORE`
+/// must not implement this, as [`Oneshot`] releases the operation after the
+/// first completion
+pub trait OneshotOperation {}
+
+/// Operations that can be consumed as a stream through
+/// [`Operation::submit_multishot`], either because the kernel posts several
+/// completions for them or to submit them again with a [`RestartPolicy`]
+pub trait MultishotOperation {}
+
+/// Implement operations built for regular entries on rings with big entries,
+/// which the kernel fills in the same way apart from the extra space
+macro_rules! widen_entries {
+    ($submission:ty, $completion:ty) => {
+        // SAFETY: forwards to the regular implementation
+        unsafe impl<O> Operation<$submission, $completion> for O
+        where
+            O: Operation,
+        {
+            type Output = O::Output;
+
+            fn build_submission(self: Pin<&mut Self>) -> $submission {
+                <Self as Operation>::build_submission(self).into()
+            }
+
+            unsafe fn process_completion(
+                self: Pin<&mut Self>,
+                entry: $completion,
+            ) -> Result<Self::Output> {
+                // SAFETY: the caller guarantees correspondence
+                unsafe { <Self as Operation>::process_completion(self, entry.into()) }
+            }
+
+            fn retry_after(self: Pin<&mut Self>, entry: &$completion) -> bool {
+                <Self as Operation>::retry_after(self, &entry.clone().into())
+            }
+
+            fn restart_policy(&self) -> RestartPolicy {
+                <Self as Operation>::restart_policy(self)
+            }
+
+            fn restartable(&self) -> bool {
+                <Self as Operation>::restartable(self)
+            }
+
+            fn detach_resources(self: Pin<&mut Self>) -> Box<dyn FnMut($completion)> {
+                let mut handler = <Self as Operation>::detach_resources(self);
+                Box::new(move |entry: $completion| handler(entry.into()))
+            }
+        }
+    };
+}
+
+widen_entries!(squeue::Entry128, cqueue::Entry);
+widen_entries!(squeue::Entry, cqueue::Entry32);
+widen_entries!(squeue::Entry128, cqueue::Entry32);
+
 pin_project_lite::pin_project! {
-    /// Future to wait for a operation that returns with a single completion
-    pub struct Oneshot<'a, O> {
-        reactor: &'a Reactor,
+    /// Future to wait for a operation that returns with a single completion,
+    /// which cancels the operation when dropped before it completed
+    pub struct Oneshot<'a, O, S = squeue::Entry, C = cqueue::Entry>
+    where
+        O: Operation<S, C>,
+        S: squeue::EntryMarker,
+        C: cqueue::EntryMarker,
+    {
+        reactor: &'a Reactor<S, C>,
         #[pin]
         operation: O,
         handle: Option<OperationId>,
+        origin: Origin,
+        in_flight: bool,
+    }
+
+    impl<O, S, C> PinnedDrop for Oneshot<'_, O, S, C>
+    where
+        O: Operation<S, C>,
+        S: squeue::EntryMarker,
+        C: cqueue::EntryMarker,
+    {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            if let Some(handle) = this.handle.filter(|_| *this.in_flight) {
+                detach(this.reactor, handle, this.operation.detach_resources());
+            }
+        }
     }
 }
 
-impl<'a, O> Oneshot<'a, O> {
-    const fn new(reactor: &'a Reactor, operation: O) -> Self {
+/// Hand an operation that nobody waits for anymore over to the reactor, and
+/// cancel it if the kernel didn't complete it yet
+fn detach<S, C>(reactor: &Reactor<S, C>, handle: OperationId, handler: Box<dyn FnMut(C)>)
+where
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+{
+    // the kernel may still use the resources until the final completion,
+    // while the slot stays reserved so the cancellation can't hit another
+    // operation
+    if !reactor.detach_operation_with(handle, handler) {
+        return;
+    }
+
+    let entry = opcode::AsyncCancel::new(handle.as_raw().try_into().unwrap()).build();
+
+    // SAFETY: we don't set any parameters that can get invalidated, while
+    // failing to submit lets the operation complete by itself
+    drop(unsafe { reactor.submit_detached(entry.into(), ()) });
+}
+
+impl<'a, O, S, C> Oneshot<'a, O, S, C>
+where
+    O: Operation<S, C>,
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+{
+    const fn new(reactor: &'a Reactor<S, C>, operation: O) -> Self {
         Self {
             reactor,
             operation,
             handle: None,
+            origin: Origin::UNKNOWN,
+            in_flight: false,
         }
     }
 
@@ -72,67 +225,128 @@ impl<'a, O> Oneshot<'a, O> {
     }
 }
 
-impl<'a, O> Future for Oneshot<'a, O>
+impl<O, S, C> Future for Oneshot<'_, O, S, C>
 where
-    O: Operation,
+    O: Operation<S, C>,
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
 {
-    type Output = Result<O::Output>;
+    type Output = std::result::Result<O::Output, OperationError>;
 
     fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
-        let this = self.project();
+        let mut this = self.project();
 
         if let Some(handle) = *this.handle {
             let entry = ready!(this.reactor.drive_operation(handle, context));
-            assert!(!cqueue::more(entry.flags()), "operation assumed as oneshot");
+            assert!(!more(&entry), "operation assumed as oneshot");
+            *this.in_flight = false;
 
-            // SAFETY: we control the submission
-            return Poll::Ready(unsafe { this.operation.process_completion(entry) });
+            if !this.operation.as_mut().retry_after(&entry) {
+                // SAFETY: we control the submission
+                let result = unsafe { this.operation.process_completion(entry) };
+                return Poll::Ready(result.map_err(|error| this.origin.error(Some(handle), error)));
+            }
         }
 
         let entry = this.operation.build_submission();
+        *this.origin = Origin::of(&entry);
 
         // SAFETY: implementation promises validity
         match unsafe { this.reactor.submit_operation(entry, context) } {
             Ok(operation) => {
                 *this.handle = Some(operation);
+                *this.in_flight = true;
                 Poll::Pending
             }
-            Err(error) => Poll::Ready(Err(error)),
+            Err(error) => Poll::Ready(Err(this.origin.error(None, error))),
         }
     }
 }
 
 pin_project_lite::pin_project! {
-    /// Future to wait for a operation that returns with a single completion
-    pub struct Multishot<'a, O> {
-        reactor: &'a Reactor,
+    /// Stream of the completions of a multishot operation, which cancels the
+    /// operation along with a pending restart delay when dropped before it
+    /// ended
+    pub struct Multishot<'a, O, S = squeue::Entry, C = cqueue::Entry>
+    where
+        O: Operation<S, C>,
+        S: squeue::EntryMarker,
+        C: cqueue::EntryMarker,
+    {
+        reactor: &'a Reactor<S, C>,
         #[pin]
         operation: O,
         handle: Option<OperationId>,
+        origin: Origin,
         finished: bool,
+        policy: Option<RestartPolicy>,
+        restarts: u32,
+        delay: Option<OperationId>,
+        timeout: Option<Box<Timespec>>,
+    }
+
+    impl<O, S, C> PinnedDrop for Multishot<'_, O, S, C>
+    where
+        O: Operation<S, C>,
+        S: squeue::EntryMarker,
+        C: cqueue::EntryMarker,
+    {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+
+            if let Some(handle) = *this.handle {
+                detach(this.reactor, handle, this.operation.detach_resources());
+            }
+
+            // the timeout submission points at the boxed duration
+            if let Some(delay) = *this.delay {
+                detach(this.reactor, delay, keep_alive(this.timeout.take()));
+            }
+        }
     }
 }
 
-impl<'a, O> Multishot<'a, O> {
-    const fn new(reactor: &'a Reactor, operation: O) -> Self {
+impl<'a, O, S, C> Multishot<'a, O, S, C>
+where
+    O: Operation<S, C>,
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+{
+    const fn new(reactor: &'a Reactor<S, C>, operation: O) -> Self {
         Self {
             reactor,
             operation,
             handle: None,
+            origin: Origin::UNKNOWN,
             finished: false,
+            policy: None,
+            restarts: 0,
+            delay: None,
+            timeout: None,
         }
     }
 
+    /// Submit the operation again when the kernel ends it according to the
+    /// policy instead of ending the stream, replacing the operation's own
+    /// [`Operation::restart_policy`]
+    #[must_use]
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
     pub const fn operation_handle(&self) -> Option<OperationId> {
         self.handle
     }
 }
 
-impl<'a, O> Stream for Multishot<'a, O>
+impl<O, S, C> Stream for Multishot<'_, O, S, C>
 where
-    O: Operation,
+    O: Operation<S, C>,
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
 {
-    type Item = Result<O::Output>;
+    type Item = std::result::Result<O::Output, OperationError>;
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         if self.finished {
@@ -143,21 +357,71 @@ where
     }
 
     fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
-        let this = self.project();
+        let mut this = self.project();
 
-        if *this.finished {
-            return Poll::Ready(None);
-        }
+        loop {
+            if *this.finished {
+                return Poll::Ready(None);
+            }
+
+            if let Some(delay) = *this.delay {
+                ready!(this.reactor.drive_operation(delay, context));
+                *this.delay = None;
+                break;
+            }
+
+            let Some(handle) = *this.handle else {
+                break;
+            };
 
-        if let Some(handle) = *this.handle {
             let entry = ready!(this.reactor.drive_operation(handle, context));
-            *this.finished = !cqueue::more(entry.flags());
+            let result = entry.clone().into().result();
+            let mut hidden = false;
+
+            if result >= 0 {
+                *this.restarts = 0;
+            }
+
+            let ended = !more(&entry);
 
             // SAFETY: we control the submission
-            return Poll::Ready(Some(unsafe { this.operation.process_completion(entry) }));
+            let output = unsafe { this.operation.as_mut().process_completion(entry) };
+
+            if ended {
+                *this.handle = None;
+
+                let policy = this
+                    .policy
+                    .get_or_insert_with(|| this.operation.restart_policy());
+
+                if this.operation.restartable() && policy.restarts_after(result, *this.restarts) {
+                    *this.restarts += 1;
+                    hidden = policy.hides(result);
+
+                    if let Some(delay) = policy.delay(*this.restarts) {
+                        let timeout = this.timeout.insert(Box::new(Timespec::from(delay)));
+                        let entry = opcode::Timeout::new(&raw const **timeout).build();
+
+                        // SAFETY: the timeout is boxed and kept until the next
+                        // one, while failing to submit just skips the delay
+                        *this.delay =
+                            unsafe { this.reactor.submit_operation(entry.into(), context) }.ok();
+                    }
+                } else {
+                    *this.finished = true;
+                }
+            }
+
+            // the restart happens on the next poll after yielding the output
+            if !hidden {
+                return Poll::Ready(Some(
+                    output.map_err(|error| this.origin.error(Some(handle), error)),
+                ));
+            }
         }
 
         let entry = this.operation.build_submission();
+        *this.origin = Origin::of(&entry);
 
         // SAFETY: implementation promises validity
         match unsafe { this.reactor.submit_operation(entry, context) } {
@@ -165,7 +429,135 @@ where
                 *this.handle = Some(operation);
                 Poll::Pending
             }
-            Err(error) => Poll::Ready(Some(Err(error))),
+            Err(error) => Poll::Ready(Some(Err(this.origin.error(None, error)))),
+        }
+    }
+}
+
+/// Errors that submitting the same operation again can't resolve, which are
+/// never restarted after unless explicitly listed in
+/// [`RestartPolicy::on_errors`]
+const TERMINAL_ERRORS: [i32; 6] = [
+    libc::ECANCELED,
+    libc::EBADF,
+    libc::EFAULT,
+    libc::EINVAL,
+    libc::ENOTSOCK,
+    libc::EOPNOTSUPP,
+];
+
+/// Restarts in a row after which [`RestartPolicy::always`] gives up by default
+const DEFAULT_MAX_RESTARTS: u32 = 16;
+
+/// When to submit a multishot operation again after the kernel ended it
+#[derive(Clone, Debug, Default)]
+enum RestartCondition {
+    #[default]
+    Never,
+    Success,
+    Errors(Vec<i32>),
+    Always,
+}
+
+/// Policy for keeping multishot streams alive when the kernel ends them, such
+/// as on `ENOBUFS` when running out of provided buffers or when the completion
+/// queue overflows
+///
+/// Every operation starts out with its own [`Operation::restart_policy`],
+/// which a policy passed to [`Multishot::restart_policy`] replaces entirely.
+/// Errors listed in [`RestartPolicy::on_errors`] are expected and hidden from
+/// the stream, while other errors are yielded before restarting so that
+/// consumers can still stop by dropping the stream.
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct RestartPolicy {
+    condition: RestartCondition,
+    max_restarts: Option<u32>,
+    backoff: Option<(Duration, Duration)>,
+}
+
+impl RestartPolicy {
+    /// End the stream along with the operation
+    pub const fn never() -> Self {
+        Self {
+            condition: RestartCondition::Never,
+            max_restarts: None,
+            backoff: None,
+        }
+    }
+
+    /// Restart only after the operation ended without an error, which is
+    /// how the kernel ends some multishot operations when it can't keep them
+    /// going, such as on completion queue overflow
+    pub const fn on_success() -> Self {
+        Self {
+            condition: RestartCondition::Success,
+            max_restarts: None,
+            backoff: None,
+        }
+    }
+
+    /// Restart only after the operation ended with one of the error codes,
+    /// without yielding those errors
+    pub fn on_errors(errors: &[i32]) -> Self {
+        Self {
+            condition: RestartCondition::Errors(errors.to_vec()),
+            ..Self::never()
+        }
+    }
+
+    /// Restart whenever the operation ended, apart from errors like `EBADF`,
+    /// `EINVAL` or `ECANCELED` that a restart can't resolve, giving up after
+    /// 16 restarts in a row unless [`RestartPolicy::max_restarts`] says
+    /// otherwise
+    pub const fn always() -> Self {
+        Self {
+            condition: RestartCondition::Always,
+            max_restarts: Some(DEFAULT_MAX_RESTARTS),
+            backoff: None,
         }
     }
+
+    /// Give up after restarting the given amount of times in a row, which is
+    /// reset by every successful completion
+    pub const fn max_restarts(mut self, restarts: u32) -> Self {
+        self.max_restarts = Some(restarts);
+        self
+    }
+
+    /// Wait before restarting, starting with the initial delay and doubling it
+    /// for every restart in a row up to the maximum
+    pub const fn backoff(mut self, initial: Duration, maximum: Duration) -> Self {
+        self.backoff = Some((initial, maximum));
+        self
+    }
+
+    fn restarts_after(&self, result: i32, restarts: u32) -> bool {
+        if self.max_restarts.is_some_and(|maximum| restarts >= maximum) {
+            return false;
+        }
+
+        match &self.condition {
+            RestartCondition::Never => false,
+            RestartCondition::Success => result >= 0,
+            RestartCondition::Errors(errors) => result.is_negative() && errors.contains(&-result),
+            RestartCondition::Always => !TERMINAL_ERRORS.contains(&-result),
+        }
+    }
+
+    /// Whether the completion ending the operation is left out of the stream
+    /// when restarting after it
+    const fn hides(&self, result: i32) -> bool {
+        matches!(self.condition, RestartCondition::Errors(_)) && result.is_negative()
+    }
+
+    /// Delay before the restart with the given number in a row
+    fn delay(&self, restart: u32) -> Option<Duration> {
+        let (initial, maximum) = self.backoff?;
+        let factor = 1_u32
+            .checked_shl(restart.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+
+        Some(initial.saturating_mul(factor).min(maximum))
+    }
 }
@@ -0,0 +1,216 @@
+use std::{
+    io::{Error, Result},
+    os::fd::{AsRawFd, BorrowedFd},
+    pin::Pin,
+};
+
+use io_uring::{cqueue, opcode, squeue, types::Fd};
+use uring_reactor::OperationId;
+
+use crate::{
+    common::patch_entry,
+    operation::{MultishotOperation, OneshotOperation, Operation},
+};
+
+/// Kernel flag for keeping a poll armed after reporting readiness
+const IORING_POLL_ADD_MULTI: u32 = 1 << 0;
+
+/// Kernel flag for replacing the event mask of an existing poll
+const IORING_POLL_UPDATE_EVENTS: u32 = 1 << 1;
+
+bitflags::bitflags! {
+    /// Readiness events to wait for and that were reported
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct Readiness: u32 {
+        const READABLE = libc::POLLIN.cast_unsigned() as u32;
+        const WRITABLE = libc::POLLOUT.cast_unsigned() as u32;
+        const PRIORITY = libc::POLLPRI.cast_unsigned() as u32;
+        const ERROR = libc::POLLERR.cast_unsigned() as u32;
+        const HANGUP = libc::POLLHUP.cast_unsigned() as u32;
+        const READ_HANGUP = libc::POLLRDHUP.cast_unsigned() as u32;
+    }
+}
+
+/// Wait for readiness of any file once
+#[must_use]
+pub struct Poll<'a> {
+    file: BorrowedFd<'a>,
+    events: Readiness,
+}
+
+impl<'a> Poll<'a> {
+    pub const fn new(file: BorrowedFd<'a>, events: Readiness) -> Self {
+        Self { file, events }
+    }
+
+    /// Keep reporting readiness until cancelled instead of completing after
+    /// the first event
+    pub const fn multishot(self) -> PollMulti<'a> {
+        PollMulti {
+            file: self.file,
+            events: self.events,
+        }
+    }
+}
+
+// SAFETY: file bound to live long enough
+unsafe impl Operation for Poll<'_> {
+    type Output = Readiness;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::PollAdd::new(Fd(self.file.as_raw_fd()), self.events.bits()).build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        readiness(&entry)
+    }
+}
+
+impl OneshotOperation for Poll<'_> {}
+
+impl MultishotOperation for Poll<'_> {}
+
+/// Wait for readiness of any file continuously, created by [`Poll::multishot`]
+/// and used with [`Operation::submit_multishot`]
+#[must_use]
+pub struct PollMulti<'a> {
+    file: BorrowedFd<'a>,
+    events: Readiness,
+}
+
+// SAFETY: file bound to live long enough
+unsafe impl Operation for PollMulti<'_> {
+    type Output = Readiness;
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::PollAdd::new(Fd(self.file.as_raw_fd()), self.events.bits())
+            .multi(true)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        readiness(&entry)
+    }
+}
+
+impl MultishotOperation for PollMulti<'_> {}
+
+#[must_use]
+pub struct PollRemove {
+    target: OperationId,
+}
+
+impl PollRemove {
+    pub const fn new(target: OperationId) -> Self {
+        Self { target }
+    }
+}
+
+// SAFETY: no parameters that could get invalidated
+unsafe impl Operation for PollRemove {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::PollRemove::new(self.target.as_raw().try_into().unwrap()).build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+}
+
+impl OneshotOperation for PollRemove {}
+
+impl MultishotOperation for PollRemove {}
+
+/// Replace the event mask of an in-flight [`Poll`] or [`PollMulti`] without
+/// rearming it
+#[must_use]
+pub struct PollUpdate {
+    target: OperationId,
+    events: Readiness,
+    multishot: bool,
+}
+
+impl PollUpdate {
+    pub const fn new(target: OperationId, events: Readiness) -> Self {
+        Self {
+            target,
+            events,
+            multishot: false,
+        }
+    }
+
+    /// Keep the poll armed after reporting readiness, which needs to be
+    /// repeated for multishot polls
+    pub const fn multishot(mut self) -> Self {
+        self.multishot = true;
+        self
+    }
+}
+
+// SAFETY: no parameters that could get invalidated
+unsafe impl Operation for PollUpdate {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        let mut flags = IORING_POLL_UPDATE_EVENTS;
+        if self.multishot {
+            flags |= IORING_POLL_ADD_MULTI;
+        }
+
+        // `io-uring` lacks a builder for poll updates, which are poll removals
+        // with the update flags in the length field and new events in the
+        // operation flags
+        let entry = opcode::PollRemove::new(self.target.as_raw().try_into().unwrap()).build();
+
+        patch_entry(entry, flags, poll_events(self.events.bits()))
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+}
+
+impl OneshotOperation for PollUpdate {}
+
+impl MultishotOperation for PollUpdate {}
+
+/// Readiness reported by a poll completion
+fn readiness(entry: &cqueue::Entry) -> Result<Readiness> {
+    if entry.result().is_negative() {
+        return Err(Error::from_raw_os_error(-entry.result()));
+    }
+
+    Ok(Readiness::from_bits_retain(entry.result().cast_unsigned()))
+}
+
+/// Convert an event mask to the kernel's layout, which swaps the halves on
+/// big endian platforms for compatibility with 16 bit masks
+const fn poll_events(events: u32) -> u32 {
+    if cfg!(target_endian = "big") {
+        events.rotate_left(16)
+    } else {
+        events
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    io::{Error, Result},
+    mem::MaybeUninit,
+    os::fd::{AsRawFd, BorrowedFd},
+    pin::Pin,
+};
+
+use io_uring::{cqueue, opcode, squeue};
+use uring_reactor::keep_alive;
+
+use crate::operation::{MultishotOperation, OneshotOperation, Operation};
+
+/// Which children to wait for
+#[derive(Clone, Copy)]
+pub enum WaitTarget<'a> {
+    Any,
+    Process(libc::pid_t),
+    Group(libc::pid_t),
+    Descriptor(BorrowedFd<'a>),
+}
+
+/// State change of a child process reported by [`Waitid`]
+#[derive(Clone, Copy, Debug)]
+pub struct ChildStatus {
+    pub pid: libc::pid_t,
+    pub uid: libc::uid_t,
+    /// One of the `CLD_*` codes describing the kind of change
+    pub code: libc::c_int,
+    /// The exit status or signal depending on the code
+    pub status: libc::c_int,
+}
+
+/// Wait for a state change of child processes, producing nothing when used
+/// with [`Waitid::no_hang`] and no child changed yet
+///
+/// Requires Linux 6.7 or newer
+#[must_use]
+pub struct Waitid<'a> {
+    target: WaitTarget<'a>,
+    options: libc::c_int,
+    information: Option<Box<MaybeUninit<libc::siginfo_t>>>,
+}
+
+impl<'a> Waitid<'a> {
+    /// Wait for children to exit
+    pub fn new(target: WaitTarget<'a>) -> Self {
+        Self {
+            target,
+            options: libc::WEXITED,
+            information: Some(Box::new(MaybeUninit::uninit())),
+        }
+    }
+
+    /// Also report children stopped by a signal
+    pub const fn stopped(mut self) -> Self {
+        self.options |= libc::WSTOPPED;
+        self
+    }
+
+    /// Also report stopped children resumed by `SIGCONT`
+    pub const fn continued(mut self) -> Self {
+        self.options |= libc::WCONTINUED;
+        self
+    }
+
+    /// Complete immediately if no child changed state yet
+    pub const fn no_hang(mut self) -> Self {
+        self.options |= libc::WNOHANG;
+        self
+    }
+
+    /// Leave the child in a waitable state so it can be reaped later
+    pub const fn no_wait(mut self) -> Self {
+        self.options |= libc::WNOWAIT;
+        self
+    }
+}
+
+// SAFETY: descriptor bound to live long enough and the information is owned
+// on the heap
+unsafe impl Operation for Waitid<'_> {
+    type Output = Option<ChildStatus>;
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        let (kind, id) = match self.target {
+            WaitTarget::Any => (libc::P_ALL, 0),
+            WaitTarget::Process(pid) => (libc::P_PID, pid.cast_unsigned()),
+            WaitTarget::Group(pid) => (libc::P_PGID, pid.cast_unsigned()),
+            WaitTarget::Descriptor(file) => (libc::P_PIDFD, file.as_raw_fd().cast_unsigned()),
+        };
+
+        let options = self.options;
+        let information = self.information.as_mut().unwrap();
+
+        // a zeroed process identifier tells that nothing changed for `WNOHANG`
+        //
+        // SAFETY: `siginfo_t` is plain data for which all zeroes are valid
+        let information = information.write(unsafe { std::mem::zeroed() });
+
+        opcode::WaitId::new(kind, id, options)
+            .infop(information)
+            .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        // SAFETY: the information was zeroed and filled in by the kernel
+        unsafe {
+            let information = self.information.as_ref().unwrap().assume_init_ref();
+            if information.si_pid() == 0 {
+                return Ok(None);
+            }
+
+            Ok(Some(ChildStatus {
+                pid: information.si_pid(),
+                uid: information.si_uid(),
+                code: information.si_code,
+                status: information.si_status(),
+            }))
+        }
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive(self.information.take())
+    }
+}
+
+impl OneshotOperation for Waitid<'_> {}
+
+impl MultishotOperation for Waitid<'_> {}
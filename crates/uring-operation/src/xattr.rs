@@ -0,0 +1,317 @@
+use std::{
+    ffi::CString,
+    io::{Error, Result},
+    os::fd::{AsRawFd, BorrowedFd},
+    pin::Pin,
+};
+
+use io_uring::{cqueue, opcode, squeue, types::Fd};
+use uring_reactor::keep_alive;
+
+use crate::operation::{MultishotOperation, OneshotOperation, Operation};
+
+/// Largest value the kernel allows for an extended attribute
+const XATTR_SIZE_MAX: usize = 1 << 16;
+
+/// Grow a value buffer after the kernel reported it as too small, unless it
+/// already reached the largest possible value size
+fn grow_after(entry: &cqueue::Entry, value: &mut Vec<u8>) -> bool {
+    // an empty buffer queries the size of the value instead
+    if value.capacity() == 0 {
+        let needed = usize::try_from(entry.result()).unwrap_or(0);
+        value.reserve_exact(needed);
+        return needed != 0;
+    }
+
+    if entry.result() != -libc::ERANGE || value.capacity() >= XATTR_SIZE_MAX {
+        return false;
+    }
+
+    let capacity = (value.capacity() * 2).clamp(64, XATTR_SIZE_MAX);
+    value.reserve_exact(capacity - value.len());
+    true
+}
+
+/// Read an extended attribute of the file at the path, retrying with a larger
+/// buffer while the value doesn't fit
+#[must_use]
+pub struct GetXattr {
+    path: CString,
+    name: CString,
+    value: Vec<u8>,
+}
+
+impl GetXattr {
+    pub const fn new(path: CString, name: CString) -> Self {
+        Self {
+            path,
+            name,
+            value: Vec::new(),
+        }
+    }
+
+    /// Start off with a buffer of the given size
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.value = Vec::with_capacity(capacity);
+        self
+    }
+}
+
+// SAFETY: path, name and value buffers are owned
+unsafe impl Operation for GetXattr {
+    type Output = Vec<u8>;
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::GetXattr::new(
+            self.name.as_ptr(),
+            self.value.as_mut_ptr().cast(),
+            self.path.as_ptr(),
+            u32::try_from(self.value.capacity()).unwrap(),
+        )
+        .build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let length: usize = entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))?;
+
+        // SAFETY: we trust the kernel to tell us how much was written into the buffer
+        unsafe {
+            self.value.set_len(length);
+        }
+
+        Ok(std::mem::take(&mut self.value))
+    }
+
+    fn retry_after(mut self: Pin<&mut Self>, entry: &cqueue::Entry) -> bool {
+        grow_after(entry, &mut self.value)
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive((
+            std::mem::take(&mut self.path),
+            std::mem::take(&mut self.name),
+            std::mem::take(&mut self.value),
+        ))
+    }
+}
+
+impl OneshotOperation for GetXattr {}
+
+impl MultishotOperation for GetXattr {}
+
+/// Read an extended attribute of an open file, retrying with a larger buffer
+/// while the value doesn't fit
+#[must_use]
+pub struct FGetXattr<'a> {
+    file: BorrowedFd<'a>,
+    name: CString,
+    value: Vec<u8>,
+}
+
+impl<'a> FGetXattr<'a> {
+    pub const fn new(file: BorrowedFd<'a>, name: CString) -> Self {
+        Self {
+            file,
+            name,
+            value: Vec::new(),
+        }
+    }
+
+    /// Start off with a buffer of the given size
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.value = Vec::with_capacity(capacity);
+        self
+    }
+}
+
+// SAFETY: file bound to live long enough and name and value buffers are owned
+unsafe impl Operation for FGetXattr<'_> {
+    type Output = Vec<u8>;
+
+    fn build_submission(mut self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::FGetXattr::new(
+            Fd(self.file.as_raw_fd()),
+            self.name.as_ptr(),
+            self.value.as_mut_ptr().cast(),
+            u32::try_from(self.value.capacity()).unwrap(),
+        )
+        .build()
+    }
+
+    unsafe fn process_completion(
+        mut self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        let length: usize = entry
+            .result()
+            .try_into()
+            .map_err(|_| Error::from_raw_os_error(-entry.result()))?;
+
+        // SAFETY: we trust the kernel to tell us how much was written into the buffer
+        unsafe {
+            self.value.set_len(length);
+        }
+
+        Ok(std::mem::take(&mut self.value))
+    }
+
+    fn retry_after(mut self: Pin<&mut Self>, entry: &cqueue::Entry) -> bool {
+        grow_after(entry, &mut self.value)
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive((
+            std::mem::take(&mut self.name),
+            std::mem::take(&mut self.value),
+        ))
+    }
+}
+
+impl OneshotOperation for FGetXattr<'_> {}
+
+impl MultishotOperation for FGetXattr<'_> {}
+
+#[must_use]
+pub struct SetXattr {
+    path: CString,
+    name: CString,
+    value: Vec<u8>,
+    flags: libc::c_int,
+}
+
+impl SetXattr {
+    pub const fn new(path: CString, name: CString, value: Vec<u8>) -> Self {
+        Self {
+            path,
+            name,
+            value,
+            flags: 0,
+        }
+    }
+
+    /// Fail if the attribute already exists
+    pub const fn create_only(mut self) -> Self {
+        self.flags = libc::XATTR_CREATE;
+        self
+    }
+
+    /// Fail if the attribute doesn't exist yet
+    pub const fn replace_only(mut self) -> Self {
+        self.flags = libc::XATTR_REPLACE;
+        self
+    }
+}
+
+// SAFETY: path, name and value buffers are owned
+unsafe impl Operation for SetXattr {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::SetXattr::new(
+            self.name.as_ptr(),
+            self.value.as_ptr().cast(),
+            self.path.as_ptr(),
+            u32::try_from(self.value.len()).unwrap(),
+        )
+        .flags(self.flags)
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive((
+            std::mem::take(&mut self.path),
+            std::mem::take(&mut self.name),
+            std::mem::take(&mut self.value),
+        ))
+    }
+}
+
+impl OneshotOperation for SetXattr {}
+
+impl MultishotOperation for SetXattr {}
+
+#[must_use]
+pub struct FSetXattr<'a> {
+    file: BorrowedFd<'a>,
+    name: CString,
+    value: Vec<u8>,
+    flags: libc::c_int,
+}
+
+impl<'a> FSetXattr<'a> {
+    pub const fn new(file: BorrowedFd<'a>, name: CString, value: Vec<u8>) -> Self {
+        Self {
+            file,
+            name,
+            value,
+            flags: 0,
+        }
+    }
+
+    /// Fail if the attribute already exists
+    pub const fn create_only(mut self) -> Self {
+        self.flags = libc::XATTR_CREATE;
+        self
+    }
+
+    /// Fail if the attribute doesn't exist yet
+    pub const fn replace_only(mut self) -> Self {
+        self.flags = libc::XATTR_REPLACE;
+        self
+    }
+}
+
+// SAFETY: file bound to live long enough and name and value buffers are owned
+unsafe impl Operation for FSetXattr<'_> {
+    type Output = ();
+
+    fn build_submission(self: Pin<&mut Self>) -> squeue::Entry {
+        opcode::FSetXattr::new(
+            Fd(self.file.as_raw_fd()),
+            self.name.as_ptr(),
+            self.value.as_ptr().cast(),
+            u32::try_from(self.value.len()).unwrap(),
+        )
+        .flags(self.flags)
+        .build()
+    }
+
+    unsafe fn process_completion(
+        self: Pin<&mut Self>,
+        entry: cqueue::Entry,
+    ) -> Result<Self::Output> {
+        if entry.result().is_negative() {
+            return Err(Error::from_raw_os_error(-entry.result()));
+        }
+
+        Ok(())
+    }
+
+    fn detach_resources(mut self: Pin<&mut Self>) -> Box<dyn FnMut(cqueue::Entry)> {
+        keep_alive((
+            std::mem::take(&mut self.name),
+            std::mem::take(&mut self.value),
+        ))
+    }
+}
+
+impl OneshotOperation for FSetXattr<'_> {}
+
+impl MultishotOperation for FSetXattr<'_> {}
@@ -0,0 +1,174 @@
+use std::{
+    future::Future,
+    io::{ErrorKind, Read as _, Write as _},
+    net::{TcpListener, TcpStream},
+    os::{
+        fd::{AsFd, AsRawFd, OwnedFd},
+        unix::net::{UnixDatagram, UnixStream},
+    },
+    pin::pin,
+    task::{Context, Waker},
+    time::{Duration, Instant},
+};
+
+use futures_core::Stream;
+use io_uring::{opcode, types::Fd, IoUring};
+use uring_operation::{Accept, Nop, Operation, Read, RecvFrom, RecvMsg};
+use uring_reactor::Reactor;
+
+/// Wait for the cancellations submitted so far to take effect
+fn settle(reactor: &Reactor) {
+    // the cancellation is submitted before the no-op, so it already took
+    // effect once the no-op completed
+    local_fifo_executor::block_on(Nop::new().submit_oneshot(reactor), || reactor.tick())
+        .unwrap()
+        .unwrap();
+}
+
+/// Poll the future once so that its operation gets submitted, drop it and
+/// wait until the cancellation took effect
+fn drop_pending(reactor: &Reactor, future: impl Future) {
+    {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(future.as_mut().poll(&mut context).is_pending());
+    }
+
+    settle(reactor);
+}
+
+#[test]
+fn dropped_oneshot_is_cancelled() {
+    let reactor = Reactor::new(IoUring::new(8).unwrap());
+    let (mut sender, mut receiver) = UnixStream::pair().unwrap();
+
+    drop_pending(
+        &reactor,
+        Read::new(receiver.as_fd(), Vec::with_capacity(16)).submit_oneshot(&reactor),
+    );
+
+    sender.write_all(b"kept").unwrap();
+    receiver.set_nonblocking(true).unwrap();
+
+    let mut buffer = [0; 16];
+    let amount = receiver.read(&mut buffer).unwrap();
+    assert_eq!(&buffer[..amount], b"kept");
+}
+
+#[test]
+fn dropped_receive_with_header_is_cancelled() {
+    let reactor = Reactor::new(IoUring::new(8).unwrap());
+    let (sender, receiver) = UnixDatagram::pair().unwrap();
+
+    drop_pending(
+        &reactor,
+        RecvFrom::new(receiver.as_fd(), Vec::with_capacity(16)).submit_oneshot(&reactor),
+    );
+
+    sender.send(b"kept").unwrap();
+    receiver.set_nonblocking(true).unwrap();
+
+    let mut buffer = [0; 16];
+    let amount = receiver.recv(&mut buffer).unwrap();
+    assert_eq!(&buffer[..amount], b"kept");
+}
+
+#[test]
+fn dropped_receive_with_control_buffer_is_cancelled() {
+    let reactor = Reactor::new(IoUring::new(8).unwrap());
+    let (sender, receiver) = UnixDatagram::pair().unwrap();
+
+    drop_pending(
+        &reactor,
+        RecvMsg::new(receiver.as_fd(), Vec::with_capacity(16))
+            .ancillary_capacity(64)
+            .submit_oneshot(&reactor),
+    );
+
+    sender.send(b"kept").unwrap();
+    receiver.set_nonblocking(true).unwrap();
+
+    let mut buffer = [0; 16];
+    let amount = receiver.recv(&mut buffer).unwrap();
+    assert_eq!(&buffer[..amount], b"kept");
+}
+
+uring_operation::operation! {
+    /// Read into an owned buffer
+    struct ReadOwned {
+        file: OwnedFd,
+        buffer: Box<[u8; 16]>,
+    }
+
+    // SAFETY: the boxed buffer is never replaced
+    unsafe build {
+        opcode::Read::new(Fd(file.as_raw_fd()), buffer.as_mut_ptr(), 16).build()
+    }
+
+    complete(result) -> Vec<u8> {
+        buffer[..result as usize].to_vec()
+    }
+}
+
+#[test]
+fn dropped_declared_operation_is_cancelled() {
+    let reactor = Reactor::new(IoUring::new(8).unwrap());
+    let (mut sender, mut receiver) = UnixStream::pair().unwrap();
+    let file = receiver.as_fd().try_clone_to_owned().unwrap();
+
+    drop_pending(
+        &reactor,
+        ReadOwned::new(file, Box::new([0; 16])).submit_oneshot(&reactor),
+    );
+
+    sender.write_all(b"kept").unwrap();
+    receiver.set_nonblocking(true).unwrap();
+
+    let mut buffer = [0; 16];
+    let amount = receiver.read(&mut buffer).unwrap();
+    assert_eq!(&buffer[..amount], b"kept");
+}
+
+#[test]
+fn dropped_multishot_closes_queued_sockets() {
+    let reactor = Reactor::new(IoUring::new(8).unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let mut queued = {
+        let mut stream = pin!(Accept::new(listener.as_fd())
+            .multishot()
+            .submit_multishot(&reactor));
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(stream.as_mut().poll_next(&mut context).is_pending());
+
+        // the accepted socket is queued without anyone taking it
+        let queued = TcpStream::connect(address).unwrap();
+        reactor.tick().unwrap();
+        queued
+    };
+
+    settle(&reactor);
+
+    // the socket accepted for the dropped stream is closed
+    queued
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut buffer = [0; 4];
+    assert_eq!(queued.read(&mut buffer).unwrap(), 0);
+
+    // and the accept doesn't take connections anymore
+    let _connected = TcpStream::connect(address).unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(1);
+
+    loop {
+        match listener.accept() {
+            Ok(_) => break,
+            Err(error) if error.kind() == ErrorKind::WouldBlock && Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(error) => panic!("connection taken by the dropped accept: {error}"),
+        }
+    }
+}
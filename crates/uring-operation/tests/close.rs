@@ -0,0 +1,31 @@
+use std::{
+    io::ErrorKind,
+    os::fd::{AsRawFd, BorrowedFd},
+};
+
+use io_uring::IoUring;
+use uring_operation::{Close, Operation, OperationExt};
+use uring_reactor::Reactor;
+
+#[test]
+fn retried_close_fails_instead_of_closing_again() {
+    let reactor = Reactor::new(IoUring::new(8).unwrap());
+    let ring = IoUring::<io_uring::squeue::Entry>::new(1).unwrap();
+
+    // SAFETY: the ring outlives the borrow
+    let file = unsafe { BorrowedFd::borrow_raw(ring.as_raw_fd()) }
+        .try_clone_to_owned()
+        .unwrap();
+
+    // closing a ring through io_uring itself fails with `EBADF`
+    let error = local_fifo_executor::block_on(
+        Close::new(file)
+            .retry_on(|error| error.raw_os_error() == Some(libc::EBADF))
+            .submit_oneshot(&reactor),
+        || reactor.tick(),
+    )
+    .unwrap()
+    .unwrap_err();
+
+    assert_eq!(error.into_io_error().kind(), ErrorKind::InvalidInput);
+}
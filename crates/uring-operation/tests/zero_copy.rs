@@ -0,0 +1,111 @@
+use std::{
+    future::poll_fn,
+    io::Read,
+    net::{TcpListener, TcpStream},
+    os::fd::AsFd,
+    pin::Pin,
+};
+
+use futures_core::Stream;
+use io_uring::{cqueue, squeue, IoUring};
+use uring_operation::{Operation, RestartPolicy, SendZc, ZeroCopy};
+use uring_reactor::Reactor;
+
+/// Wait for the next item of a stream while ticking the reactor
+fn next<S, C, T>(reactor: &Reactor<S, C>, mut stream: Pin<&mut T>) -> Option<T::Item>
+where
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+    T: Stream,
+{
+    local_fifo_executor::block_on(
+        poll_fn(|context| stream.as_mut().poll_next(context)),
+        || reactor.tick(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn sent_then_released() {
+    let reactor = Reactor::new(IoUring::new(8).unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+
+    // restarting must not resubmit the operation after it released its buffer
+    let mut stream = std::pin::pin!(SendZc::new(client.as_fd(), b"zero-copy".to_vec())
+        .submit_multishot(&reactor)
+        .restart_policy(RestartPolicy::always()));
+
+    let Some(Ok(ZeroCopy::Sent(sent))) = next(&reactor, stream.as_mut()) else {
+        panic!("expected the sent amount first");
+    };
+    assert_eq!(sent, 9);
+
+    let Some(Ok(ZeroCopy::Released { buffers, .. })) = next(&reactor, stream.as_mut()) else {
+        panic!("expected the buffer to be released afterwards");
+    };
+    assert_eq!(buffers, b"zero-copy");
+
+    assert!(next(&reactor, stream.as_mut()).is_none());
+
+    let mut received = [0; 9];
+    server.read_exact(&mut received).unwrap();
+    assert_eq!(&received, b"zero-copy");
+}
+
+#[test]
+fn dropped_before_released() {
+    let reactor = Reactor::new(IoUring::new(8).unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+
+    {
+        let mut stream = std::pin::pin!(
+            SendZc::new(client.as_fd(), b"zero-copy".to_vec()).submit_multishot(&reactor)
+        );
+
+        let Some(Ok(ZeroCopy::Sent(sent))) = next(&reactor, stream.as_mut()) else {
+            panic!("expected the sent amount first");
+        };
+        assert_eq!(sent, 9);
+    }
+
+    // the reactor keeps the buffer until the kernel releases it
+    let mut received = [0; 9];
+    server.read_exact(&mut received).unwrap();
+    assert_eq!(&received, b"zero-copy");
+}
+
+#[test]
+fn released_on_big_entries() {
+    let ring = IoUring::<squeue::Entry128, cqueue::Entry32>::builder()
+        .build(8)
+        .unwrap();
+    let reactor = Reactor::new(ring);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+
+    // the stream only ends once the big completion stops flagging more
+    let mut stream = std::pin::pin!(
+        SendZc::new(client.as_fd(), b"zero-copy".to_vec()).submit_multishot(&reactor)
+    );
+
+    let Some(Ok(ZeroCopy::Sent(sent))) = next(&reactor, stream.as_mut()) else {
+        panic!("expected the sent amount first");
+    };
+    assert_eq!(sent, 9);
+
+    let Some(Ok(ZeroCopy::Released { buffers, .. })) = next(&reactor, stream.as_mut()) else {
+        panic!("expected the buffer to be released afterwards");
+    };
+    assert_eq!(buffers, b"zero-copy");
+
+    assert!(next(&reactor, stream.as_mut()).is_none());
+
+    let mut received = [0; 9];
+    server.read_exact(&mut received).unwrap();
+    assert_eq!(&received, b"zero-copy");
+}
@@ -1,4 +1,5 @@
 use std::{
+    any::Any,
     collections::VecDeque,
     io::{Error, ErrorKind::Other, Result},
     task::{Context, Poll, Waker},
@@ -8,15 +9,24 @@ use danger_cell::DangerCell;
 use io_uring::{cqueue, squeue, IoUring};
 use slab::Slab;
 
-/// Simple IO reactor for making `io_uring` operations
+/// Simple IO reactor for making `io_uring` operations, optionally with big
+/// submission and completion entries for passthrough commands
 #[must_use]
-pub struct Reactor {
-    ring: DangerCell<IoUring>,
-    operations: DangerCell<Slab<State>>,
+pub struct Reactor<S = squeue::Entry, C = cqueue::Entry>
+where
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+{
+    ring: DangerCell<IoUring<S, C>>,
+    operations: DangerCell<Slab<State<C>>>,
 }
 
-impl Reactor {
-    pub const fn new(ring: IoUring) -> Self {
+impl<S, C> Reactor<S, C>
+where
+    S: squeue::EntryMarker,
+    C: cqueue::EntryMarker,
+{
+    pub const fn new(ring: IoUring<S, C>) -> Self {
         Self {
             ring: DangerCell::new(ring),
             operations: DangerCell::new(Slab::new()),
@@ -37,29 +47,142 @@ impl Reactor {
     /// # Errors
     ///
     /// If submitting the entry fails
-    pub unsafe fn submit_operation(
+    pub unsafe fn submit_operation(&self, entry: S, context: &mut Context) -> Result<OperationId> {
+        // SAFETY: the caller guarantees validity
+        unsafe { self.push(entry, State::Waiting(context.waker().clone())) }
+    }
+
+    /// Make a submission that nobody waits for, keeping the resources alive
+    /// until its final completion arrives
+    ///
+    /// # Safety
+    ///
+    /// Submission parameters must remain valid for the duration of the
+    /// operation, which the resources can take care of
+    ///
+    /// # Panics
+    ///
+    /// If the created operation's index doesn't fit into user data
+    ///
+    /// # Errors
+    ///
+    /// If submitting the entry fails
+    pub unsafe fn submit_detached(&self, entry: S, resources: impl Any) -> Result<()> {
+        // SAFETY: the caller guarantees validity
+        unsafe { self.push(entry, State::Detached(keep_alive(resources))) }.map(drop)
+    }
+
+    /// Stop waiting for an in-flight operation, keeping the resources it uses
+    /// alive until its final completion arrives
+    ///
+    /// Returns whether the operation is still in flight, in which case its
+    /// slot stays reserved until the final completion so that cancelling it
+    /// by its handle can't hit another operation
+    ///
+    /// # Panics
+    ///
+    /// If the operation handle is invalid
+    pub fn detach_operation(&self, operation: OperationId, resources: impl Any) -> bool {
+        self.detach_operation_with(operation, keep_alive(resources))
+    }
+
+    /// Stop waiting for an in-flight operation like
+    /// [`Reactor::detach_operation`], passing completions that weren't
+    /// claimed yet and those arriving later to the handler, which can release
+    /// what they carry such as accepted file descriptors
+    ///
+    /// # Panics
+    ///
+    /// If the operation handle is invalid
+    pub fn detach_operation_with(
         &self,
-        entry: squeue::Entry,
-        context: &mut Context,
-    ) -> Result<OperationId> {
-        let index = self
-            .operations
-            .assume_unique_access()
-            .insert(State::Waiting(context.waker().clone()));
+        operation: OperationId,
+        mut handler: Box<dyn FnMut(C)>,
+    ) -> bool {
+        let mut guard = self.operations.assume_unique_access();
+        let slot = guard.get_mut(operation.as_raw()).unwrap();
+
+        let (entries, finished) = match slot {
+            State::Waiting(_) => (VecDeque::new(), false),
+            State::Completed(entry) => (VecDeque::from([entry.clone()]), !more(entry)),
+            State::Unclaimed(entries) => {
+                let finished = entries.back().is_some_and(|entry| !more(entry));
+                (std::mem::take(entries), finished)
+            }
+            State::Detached(_) => panic!("operation is already detached"),
+        };
+
+        drop(guard);
+
+        // handled without holding on to the operations in case releasing
+        // what the completions carry uses the reactor
+        for entry in entries {
+            handler(entry);
+        }
+
+        let mut guard = self.operations.assume_unique_access();
+        if finished {
+            guard.remove(operation.as_raw());
+        } else {
+            guard[operation.as_raw()] = State::Detached(handler);
+        }
+
+        !finished
+    }
+
+    /// Make room for the given amount of entries in the submission queue,
+    /// submitting queued entries if necessary, so that the next submissions
+    /// up to that amount don't get split across calls into the kernel as is
+    /// required for linked entries
+    ///
+    /// # Errors
+    ///
+    /// If submitting fails or the submission queue can't hold that many
+    /// entries at once
+    pub fn reserve_submissions(&self, entries: usize) -> Result<()> {
+        let mut guard = self.ring.assume_unique_access();
+        let (submitter, mut submission, _) = guard.split();
+
+        if submission.capacity() - submission.len() < entries {
+            submitter.submit()?;
+            submission.sync();
+        }
+
+        if submission.capacity() - submission.len() < entries {
+            return Err(Error::new(
+                Other,
+                "submission queue too small for the entries",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// See [`Reactor::submit_operation`]
+    unsafe fn push(&self, mut entry: S, state: State<C>) -> Result<OperationId> {
+        let index = self.operations.assume_unique_access().insert(state);
 
-        let entry = entry.user_data(index.try_into().unwrap());
+        entry.set_user_data(index.try_into().unwrap());
 
         let mut guard = self.ring.assume_unique_access();
         let (submitter, mut submission, _) = guard.split();
 
         // SAFETY: the caller guarantees validity
-        unsafe {
+        let pushed = unsafe {
             submission.push(&entry).or_else(|_| {
                 submitter.submit()?;
+                submission.sync();
                 submission
                     .push(&entry)
                     .map_err(|error| Error::new(Other, error))
-            })?;
+            })
+        };
+
+        if let Err(error) = pushed {
+            self.operations.assume_unique_access().remove(index);
+            return Err(error);
         }
 
         Ok(OperationId::from_raw(index))
@@ -70,11 +193,7 @@ impl Reactor {
     /// # Panics
     ///
     /// If the operation handle is invalid
-    pub fn drive_operation(
-        &self,
-        operation: OperationId,
-        context: &mut Context,
-    ) -> Poll<cqueue::Entry> {
+    pub fn drive_operation(&self, operation: OperationId, context: &mut Context) -> Poll<C> {
         let mut guard = self.operations.assume_unique_access();
         let slot = guard.get_mut(operation.as_raw()).unwrap();
 
@@ -87,7 +206,7 @@ impl Reactor {
                 Poll::Pending
             }
             State::Completed(entry) => {
-                if !cqueue::more(entry.flags()) {
+                if !more(entry) {
                     return Poll::Ready(guard.remove(operation.as_raw()).assume_as_completed());
                 }
 
@@ -106,7 +225,7 @@ impl Reactor {
                     return Poll::Ready(entry);
                 }
 
-                if !cqueue::more(entry.flags()) {
+                if !more(&entry) {
                     guard.remove(operation.as_raw());
                     return Poll::Ready(entry);
                 }
@@ -114,9 +233,47 @@ impl Reactor {
                 *slot = State::Waiting(context.waker().clone());
                 Poll::Ready(entry)
             }
+            State::Detached(_) => panic!("operation is detached"),
+        }
+    }
+
+    /// Register a ring of provided buffers for operations to select from
+    ///
+    /// # Safety
+    ///
+    /// The ring memory must remain valid until the group is unregistered
+    ///
+    /// # Errors
+    ///
+    /// If the kernel rejects the registration
+    pub unsafe fn register_buffer_ring(
+        &self,
+        address: u64,
+        entries: u16,
+        group: u16,
+    ) -> Result<()> {
+        let guard = self.ring.assume_unique_access();
+
+        // SAFETY: the caller guarantees validity
+        unsafe {
+            guard
+                .submitter()
+                .register_buf_ring_with_flags(address, entries, group, 0)
         }
     }
 
+    /// Unregister a previously registered ring of provided buffers
+    ///
+    /// # Errors
+    ///
+    /// If the group isn't registered
+    pub fn unregister_buffer_ring(&self, group: u16) -> Result<()> {
+        self.ring
+            .assume_unique_access()
+            .submitter()
+            .unregister_buf_ring(group)
+    }
+
     /// Submit entries to the kernel and process completions, in turn waking
     /// up blocked futures
     ///
@@ -129,19 +286,19 @@ impl Reactor {
     /// If synchronizing with the kernel fails
     pub fn tick(&self) -> Result<()> {
         let mut guard = self.ring.assume_unique_access();
-        let (submitter, submission, completion) = guard.split();
+        let (submitter, submission, mut completion) = guard.split();
 
         if completion.is_empty() {
             submitter.submit_and_wait(1)?;
+            completion.sync();
         } else if !submission.is_empty() {
             submitter.submit()?;
         }
 
         for entry in completion {
+            let index = entry.user_data().try_into().unwrap();
             let mut guard = self.operations.assume_unique_access();
-            let slot = guard
-                .get_mut(entry.user_data().try_into().unwrap())
-                .unwrap();
+            let slot = guard.get_mut(index).unwrap();
 
             match slot {
                 State::Waiting(_) => {
@@ -158,6 +315,24 @@ impl Reactor {
                     entries.push_back(entry);
                 }
                 State::Unclaimed(entries) => entries.push_back(entry),
+                State::Detached(_) => {
+                    let finished = !more(&entry);
+                    let mut handler = if finished {
+                        guard.remove(index)
+                    } else {
+                        std::mem::replace(slot, State::Unclaimed(VecDeque::new()))
+                    }
+                    .assume_as_detached();
+                    drop(guard);
+
+                    // handled without holding on to the operations in case
+                    // releasing resources uses the reactor
+                    handler(entry);
+
+                    if !finished {
+                        self.operations.assume_unique_access()[index] = State::Detached(handler);
+                    }
+                }
             }
         }
 
@@ -166,7 +341,7 @@ impl Reactor {
 }
 
 /// Strongly typed index referring to a [`State`] instance
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[must_use]
 pub struct OperationId(usize);
 
@@ -181,14 +356,27 @@ impl OperationId {
     }
 }
 
+/// Whether the kernel will post more completions for the same operation
+#[must_use]
+pub fn more<C: cqueue::EntryMarker>(entry: &C) -> bool {
+    cqueue::more(entry.clone().into().flags())
+}
+
 /// Internal state of an submitted operation
-enum State {
+enum State<C> {
     Waiting(Waker),
-    Completed(cqueue::Entry),
-    Unclaimed(VecDeque<cqueue::Entry>),
+    Completed(C),
+    Unclaimed(VecDeque<C>),
+    Detached(Box<dyn FnMut(C)>),
 }
 
-impl State {
+/// Handler for detached operations that only keeps their resources alive
+/// until the final completion arrives
+pub fn keep_alive<C>(resources: impl Any) -> Box<dyn FnMut(C)> {
+    Box::new(move |_| _ = &resources)
+}
+
+impl<C> State<C> {
     fn assume_as_waiting(self) -> Waker {
         if let Self::Waiting(waker) = self {
             return waker;
@@ -197,7 +385,7 @@ impl State {
         panic!("expected to be in the waiting state");
     }
 
-    fn assume_as_completed(self) -> cqueue::Entry {
+    fn assume_as_completed(self) -> C {
         if let Self::Completed(entry) = self {
             return entry;
         }
@@ -205,11 +393,19 @@ impl State {
         panic!("expected to be in the completed state");
     }
 
-    fn assume_as_mut_unclaimed(&mut self) -> &mut VecDeque<cqueue::Entry> {
+    fn assume_as_mut_unclaimed(&mut self) -> &mut VecDeque<C> {
         if let Self::Unclaimed(entries) = self {
             return entries;
         }
 
         panic!("expected to be in the unclaimed state");
     }
+
+    fn assume_as_detached(self) -> Box<dyn FnMut(C)> {
+        if let Self::Detached(handler) = self {
+            return handler;
+        }
+
+        panic!("expected to be in the detached state");
+    }
 }
@@ -1,12 +1,20 @@
-use std::{io::Result, net::SocketAddr, num::NonZeroUsize, os::fd::AsFd, rc::Rc};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    future::poll_fn,
+    io::Result,
+    net::SocketAddr,
+    num::NonZeroUsize,
+    rc::Rc,
+};
 
 use clap::Parser;
+use futures_core::Stream;
 use hyper::{header::CONTENT_TYPE, server::conn::http1::Builder, Response, StatusCode};
 use io_uring::IoUring;
 use local_fifo_executor::Executor;
 use socket2::{Protocol, SockAddr, Socket, Type};
-use uring_adapter::PollIo;
-use uring_operation::{Accept, Operation};
+use uring_adapter::{net::TcpListener, PollIo};
 use uring_reactor::Reactor;
 
 #[derive(Debug, Parser)]
@@ -19,6 +27,14 @@ struct Arguments {
     backlog: i32,
     #[arg(long, env, default_value = "[::]:8080")]
     address: SocketAddr,
+    /// Read through `io_uring` into an internal buffer of this size instead
+    /// of native reads
+    #[arg(long, env)]
+    read_buffer: Option<NonZeroUsize>,
+    /// Collect writes in an internal buffer of this size instead of the
+    /// default
+    #[arg(long, env)]
+    write_buffer: Option<NonZeroUsize>,
 }
 
 fn start(arguments: &Arguments, index: usize) -> Result<()> {
@@ -40,19 +56,31 @@ fn start(arguments: &Arguments, index: usize) -> Result<()> {
     socket.bind(&address)?;
     socket.listen(arguments.backlog)?;
 
-    let actual = socket.local_addr()?.as_socket().unwrap();
-    println!("worker {index} listening on {actual}");
+    let listener = TcpListener::from_std(reactor.clone(), socket.into())?;
+    println!("worker {index} listening on {}", listener.local_addr()?);
 
     let task = executor.spawn(async {
-        loop {
-            let (stream, address) = Accept::new(socket.as_fd())
-                .non_blocking_socket()
-                .submit_oneshot(&reactor)
-                .await?;
+        let mut incoming = std::pin::pin!(listener.incoming());
+
+        while let Some(result) = poll_fn(|context| incoming.as_mut().poll_next(context)).await {
+            let (stream, address) = match result {
+                Ok((stream, address)) => (stream, Peer(address)),
+                Err(error) => {
+                    eprintln!("worker {index}: failed to accept connection: {error}");
+                    continue;
+                }
+            };
+
+            let mut io = PollIo::from(stream);
+            if let Some(capacity) = arguments.read_buffer {
+                io = io.with_read_buffer(capacity.get());
+            }
+            if let Some(capacity) = arguments.write_buffer {
+                io = io.with_write_buffer(capacity.get());
+            }
 
-            let address = address.as_socket().unwrap();
             let connection = Builder::new().serve_connection(
-                PollIo::new(reactor.clone(), stream),
+                io,
                 hyper::service::service_fn(move |_| async move {
                     let message = format!("Hello to you {address} from worker {index}\n");
 
@@ -64,13 +92,18 @@ fn start(arguments: &Arguments, index: usize) -> Result<()> {
             );
 
             executor
-                .spawn(async {
+                .spawn(async move {
                     if let Err(error) = connection.await {
-                        eprintln!("connection error from {address}: {error}");
+                        eprintln!(
+                            "worker {index}: connection from {address}: {}",
+                            report(&error)
+                        );
                     }
                 })
                 .detach();
         }
+
+        Ok(())
     });
 
     local_fifo_executor::block_on(task, || {
@@ -79,6 +112,28 @@ fn start(arguments: &Arguments, index: usize) -> Result<()> {
     })?
 }
 
+/// Peer address for messages, which is unknown if the peer disconnected right
+/// after connecting
+#[derive(Clone, Copy)]
+struct Peer(Option<SocketAddr>);
+
+impl Display for Peer {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self.0 {
+            Some(address) => address.fmt(formatter),
+            None => formatter.write_str("unknown peer"),
+        }
+    }
+}
+
+/// Render an error along with the chain of errors that caused it
+fn report(error: &(dyn Error + 'static)) -> String {
+    std::iter::successors(Some(error), |error| Error::source(*error))
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
 fn main() -> Result<()> {
     let arguments = Arguments::parse();
 